@@ -0,0 +1,5 @@
+/// The string type used throughout Bootil.
+///
+/// In the C++ library this is a typedef of `std::string`, so here it is
+/// simply a `String`.
+pub type BString = String;
@@ -0,0 +1,431 @@
+use std::io;
+
+use crate::bstring::BString;
+
+/// Plain old data that can be copied in and out of a buffer byte for byte.
+///
+/// Values are stored in native byte order, the same way the C++ library
+/// memcpy's them. File formats should use `BinaryRead` and `BinaryWrite`
+/// instead, which always use little-endian.
+pub trait Primitive: Copy + Default {
+    const SIZE: usize;
+
+    fn to_bytes(self, out: &mut Vec<u8>);
+    fn from_bytes(bytes: &[u8]) -> Self;
+}
+
+macro_rules! impl_primitive {
+    ($($t:ty),*) => {
+        $(
+            impl Primitive for $t {
+                const SIZE: usize = std::mem::size_of::<$t>();
+
+                fn to_bytes(self, out: &mut Vec<u8>) {
+                    out.extend_from_slice(&self.to_ne_bytes());
+                }
+
+                fn from_bytes(bytes: &[u8]) -> Self {
+                    let mut raw = [0u8; std::mem::size_of::<$t>()];
+                    raw.copy_from_slice(&bytes[..Self::SIZE]);
+                    <$t>::from_ne_bytes(raw)
+                }
+            }
+        )*
+    };
+}
+
+impl_primitive!(u8, i8, u16, i16, u32, i32, u64, i64, f32, f64);
+
+/// A growable block of memory with a read/write position.
+///
+/// Writing past the end grows the buffer. `get_written` is the high water
+/// mark of everything that has been written so far, which is what gets saved
+/// when the buffer is written to disk.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct Buffer {
+    data: Vec<u8>,
+    pos: usize,
+}
+
+/// Bootil distinguishes between fixed and self-growing buffers. Every buffer
+/// here grows, so the two names are interchangeable.
+pub type AutoBuffer = Buffer;
+
+impl Buffer {
+    pub fn new() -> Buffer {
+        Buffer::default()
+    }
+
+    pub fn with_capacity(capacity: usize) -> Buffer {
+        Buffer {
+            data: Vec::with_capacity(capacity),
+            pos: 0,
+        }
+    }
+
+    /// Wraps existing bytes. The position starts at 0.
+    pub fn from_vec(data: Vec<u8>) -> Buffer {
+        Buffer { data, pos: 0 }
+    }
+
+    pub fn into_vec(self) -> Vec<u8> {
+        self.data
+    }
+
+    pub fn clear(&mut self) {
+        self.data.clear();
+        self.pos = 0;
+    }
+
+    pub fn get_pos(&self) -> usize {
+        self.pos
+    }
+
+    /// Moves the position, clamped to the written size.
+    pub fn set_pos(&mut self, pos: usize) {
+        self.pos = pos.min(self.data.len());
+    }
+
+    pub fn get_written(&self) -> usize {
+        self.data.len()
+    }
+
+    /// Number of bytes left between the position and the end of the data.
+    pub fn get_remaining(&self) -> usize {
+        self.data.len() - self.pos
+    }
+
+    pub fn eof(&self) -> bool {
+        self.pos >= self.data.len()
+    }
+
+    /// Everything written so far.
+    pub fn get_base(&self) -> &[u8] {
+        &self.data
+    }
+
+    /// Writes `data` at the current position, growing as needed.
+    pub fn write(&mut self, data: &[u8]) {
+        let end = self.pos + data.len();
+        if end > self.data.len() {
+            self.data.resize(end, 0);
+        }
+        self.data[self.pos..end].copy_from_slice(data);
+        self.pos = end;
+    }
+
+    pub fn write_type<T: Primitive>(&mut self, value: T) {
+        let mut raw = Vec::with_capacity(T::SIZE);
+        value.to_bytes(&mut raw);
+        self.write(&raw);
+    }
+
+    /// Writes the string followed by a NUL terminator.
+    pub fn write_string(&mut self, value: &str) {
+        self.write(value.as_bytes());
+        self.write(&[0]);
+    }
+
+    /// Appends everything written to `other`.
+    pub fn write_buffer(&mut self, other: &Buffer) {
+        self.write(other.get_base());
+    }
+
+    /// Fills `out` from the current position. Returns false, reading
+    /// nothing, if there aren't enough bytes left.
+    pub fn read(&mut self, out: &mut [u8]) -> bool {
+        if self.get_remaining() < out.len() {
+            return false;
+        }
+        out.copy_from_slice(&self.data[self.pos..self.pos + out.len()]);
+        self.pos += out.len();
+        true
+    }
+
+    /// Reads a value, or returns the default (zero) when past the end.
+    pub fn read_type<T: Primitive>(&mut self) -> T {
+        if self.get_remaining() < T::SIZE {
+            self.pos = self.data.len();
+            return T::default();
+        }
+        let value = T::from_bytes(&self.data[self.pos..]);
+        self.pos += T::SIZE;
+        value
+    }
+
+    /// Reads up to (and skips) the next NUL, or to the end of the buffer.
+    pub fn read_string(&mut self) -> BString {
+        let rest = &self.data[self.pos..];
+        let len = rest.iter().position(|&b| b == 0).unwrap_or(rest.len());
+        let value = String::from_utf8_lossy(&rest[..len]).into_owned();
+        self.pos = (self.pos + len + 1).min(self.data.len());
+        value
+    }
+}
+
+impl io::Write for Buffer {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        Buffer::write(self, buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+impl io::Read for Buffer {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let count = buf.len().min(self.get_remaining());
+        buf[..count].copy_from_slice(&self.data[self.pos..self.pos + count]);
+        self.pos += count;
+        Ok(count)
+    }
+
+    /// Fails without moving the position if there aren't enough bytes left.
+    fn read_exact(&mut self, buf: &mut [u8]) -> io::Result<()> {
+        if self.get_remaining() < buf.len() {
+            return Err(io::Error::new(
+                io::ErrorKind::UnexpectedEof,
+                format!(
+                    "tried to read {} bytes at offset {}, only {} left",
+                    buf.len(),
+                    self.pos,
+                    self.get_remaining()
+                ),
+            ));
+        }
+        Buffer::read(self, buf);
+        Ok(())
+    }
+}
+
+/// The error inside the `io::Error` from `read_cstring` when the string is
+/// longer than allowed.
+#[derive(Debug)]
+pub struct StringTooLong {
+    pub max_len: usize,
+}
+
+impl std::fmt::Display for StringTooLong {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "string longer than {} bytes", self.max_len)
+    }
+}
+
+impl std::error::Error for StringTooLong {}
+
+/// Reads values in a fixed layout: little-endian, and strings terminated by
+/// a NUL. Running out of data is an `UnexpectedEof` error.
+///
+/// Implemented for everything readable, `Buffer` included.
+pub trait BinaryRead: io::Read {
+    fn read_u8(&mut self) -> io::Result<u8> {
+        let mut raw = [0u8; 1];
+        self.read_exact(&mut raw)?;
+        Ok(raw[0])
+    }
+
+    fn read_u32(&mut self) -> io::Result<u32> {
+        let mut raw = [0u8; 4];
+        self.read_exact(&mut raw)?;
+        Ok(u32::from_le_bytes(raw))
+    }
+
+    fn read_i32(&mut self) -> io::Result<i32> {
+        let mut raw = [0u8; 4];
+        self.read_exact(&mut raw)?;
+        Ok(i32::from_le_bytes(raw))
+    }
+
+    fn read_u64(&mut self) -> io::Result<u64> {
+        let mut raw = [0u8; 8];
+        self.read_exact(&mut raw)?;
+        Ok(u64::from_le_bytes(raw))
+    }
+
+    fn read_i64(&mut self) -> io::Result<i64> {
+        let mut raw = [0u8; 8];
+        self.read_exact(&mut raw)?;
+        Ok(i64::from_le_bytes(raw))
+    }
+
+    /// Reads up to and including the next NUL, and returns the bytes before
+    /// it. Strings longer than `max_len` bytes are an `InvalidData` error
+    /// holding `StringTooLong`.
+    fn read_cstring_bytes(&mut self, max_len: usize) -> io::Result<Vec<u8>> {
+        let mut bytes = Vec::new();
+        loop {
+            let byte = self.read_u8()?;
+            if byte == 0 {
+                break;
+            }
+            if bytes.len() == max_len {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    StringTooLong { max_len },
+                ));
+            }
+            bytes.push(byte);
+        }
+        Ok(bytes)
+    }
+
+    /// `read_cstring_bytes` for strings that have to be UTF-8. Anything else
+    /// is an `InvalidData` error rather than being quietly changed.
+    fn read_cstring(&mut self, max_len: usize) -> io::Result<BString> {
+        let bytes = self.read_cstring_bytes(max_len)?;
+        String::from_utf8(bytes).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+    }
+}
+
+impl<R: io::Read + ?Sized> BinaryRead for R {}
+
+/// Writes values in the layout `BinaryRead` reads them.
+///
+/// Implemented for everything writable, `Buffer` included.
+pub trait BinaryWrite: io::Write {
+    fn write_u8(&mut self, value: u8) -> io::Result<()> {
+        self.write_all(&[value])
+    }
+
+    fn write_u32(&mut self, value: u32) -> io::Result<()> {
+        self.write_all(&value.to_le_bytes())
+    }
+
+    fn write_i32(&mut self, value: i32) -> io::Result<()> {
+        self.write_all(&value.to_le_bytes())
+    }
+
+    fn write_u64(&mut self, value: u64) -> io::Result<()> {
+        self.write_all(&value.to_le_bytes())
+    }
+
+    fn write_i64(&mut self, value: i64) -> io::Result<()> {
+        self.write_all(&value.to_le_bytes())
+    }
+
+    /// Writes the string and a NUL. A string with a NUL of its own couldn't
+    /// be read back, so it's an `InvalidInput` error and nothing is written.
+    fn write_cstring(&mut self, value: &str) -> io::Result<()> {
+        if value.contains('\0') {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "string contains a NUL",
+            ));
+        }
+        self.write_all(value.as_bytes())?;
+        self.write_all(&[0])
+    }
+}
+
+impl<W: io::Write + ?Sized> BinaryWrite for W {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_write_read_round_trip() {
+        let mut buffer = AutoBuffer::new();
+        buffer.write(b"GMAD");
+        buffer.write_type(3u8);
+        buffer.write_type(0x1122_3344_5566_7788u64);
+        buffer.write_string("hello");
+        buffer.write_type(-1i32);
+        assert_eq!(buffer.get_written(), 4 + 1 + 8 + 6 + 4);
+
+        buffer.set_pos(0);
+        let mut ident = [0u8; 4];
+        assert!(buffer.read(&mut ident));
+        assert_eq!(&ident, b"GMAD");
+        assert_eq!(buffer.read_type::<u8>(), 3);
+        assert_eq!(buffer.read_type::<u64>(), 0x1122_3344_5566_7788);
+        assert_eq!(buffer.read_string(), "hello");
+        assert_eq!(buffer.read_type::<i32>(), -1);
+        assert!(buffer.eof());
+    }
+
+    #[test]
+    fn test_read_past_end() {
+        let mut buffer = Buffer::from_vec(vec![1, 2]);
+        assert_eq!(buffer.read_type::<u32>(), 0);
+        assert!(buffer.eof());
+        assert_eq!(buffer.read_string(), "");
+    }
+
+    #[test]
+    fn test_binary_round_trip() {
+        let mut buffer = AutoBuffer::new();
+        buffer.write_u8(3).unwrap();
+        buffer.write_u32(0x1122_3344).unwrap();
+        buffer.write_i32(-2).unwrap();
+        buffer.write_u64(0x1122_3344_5566_7788).unwrap();
+        buffer.write_i64(-3).unwrap();
+        buffer.write_cstring("hello").unwrap();
+        assert!(buffer.write_cstring("a\0b").is_err());
+        assert_eq!(buffer.get_written(), 1 + 4 + 4 + 8 + 8 + 6);
+        // Little-endian whatever the platform
+        assert_eq!(&buffer.get_base()[1..5], &[0x44, 0x33, 0x22, 0x11]);
+
+        buffer.set_pos(0);
+        assert_eq!(buffer.read_u8().unwrap(), 3);
+        assert_eq!(buffer.read_u32().unwrap(), 0x1122_3344);
+        assert_eq!(buffer.read_i32().unwrap(), -2);
+        assert_eq!(buffer.read_u64().unwrap(), 0x1122_3344_5566_7788);
+        assert_eq!(buffer.read_i64().unwrap(), -3);
+        assert_eq!(buffer.read_cstring(64).unwrap(), "hello");
+        assert!(buffer.eof());
+    }
+
+    #[test]
+    fn test_binary_errors() {
+        let mut buffer = Buffer::from_vec(vec![1, 2, 3]);
+        let err = buffer.read_u32().unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::UnexpectedEof);
+        assert_eq!(
+            err.to_string(),
+            "tried to read 4 bytes at offset 0, only 3 left"
+        );
+        // Nothing was consumed
+        assert_eq!(buffer.get_pos(), 0);
+        assert_eq!(buffer.read_u8().unwrap(), 1);
+
+        let mut buffer = Buffer::from_vec(b"abc".to_vec());
+        assert_eq!(
+            buffer.read_cstring(64).unwrap_err().kind(),
+            io::ErrorKind::UnexpectedEof
+        );
+
+        let mut buffer = Buffer::from_vec(b"abcdef\0".to_vec());
+        let err = buffer.read_cstring(4).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+        assert!(err.get_ref().unwrap().is::<StringTooLong>());
+
+        // Not UTF-8: refused, but the bytes can still be had
+        let mut buffer = Buffer::from_vec(b"caf\xe9\0".to_vec());
+        assert_eq!(
+            buffer.read_cstring(64).unwrap_err().kind(),
+            io::ErrorKind::InvalidData
+        );
+        buffer.set_pos(0);
+        assert_eq!(buffer.read_cstring_bytes(64).unwrap(), b"caf\xe9");
+        assert!(buffer.eof());
+
+        // Works on any reader, not just buffers
+        let mut slice: &[u8] = &[0x78, 0x56, 0x34, 0x12, b'x', 0];
+        assert_eq!(slice.read_u32().unwrap(), 0x1234_5678);
+        assert_eq!(slice.read_cstring(64).unwrap(), "x");
+        assert!(slice.read_u8().is_err());
+    }
+
+    #[test]
+    fn test_overwrite_keeps_size() {
+        let mut buffer = Buffer::new();
+        buffer.write(b"abcdef");
+        buffer.set_pos(2);
+        buffer.write(b"XY");
+        assert_eq!(buffer.get_base(), b"abXYef");
+        assert_eq!(buffer.get_pos(), 4);
+    }
+}
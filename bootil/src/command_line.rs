@@ -0,0 +1,131 @@
+//! Process-wide access to the command line, modelled on Bootil's
+//! `CommandLine` class.
+//!
+//! Switches are arguments starting with `-`; the value of a switch is the
+//! argument that follows it.
+
+use std::sync::RwLock;
+
+use crate::bstring::BString;
+
+static ARGS: RwLock<Vec<BString>> = RwLock::new(Vec::new());
+
+/// Stores the arguments, not including the program name.
+pub fn set<I, S>(args: I)
+where
+    I: IntoIterator<Item = S>,
+    S: Into<BString>,
+{
+    let mut stored = ARGS.write().unwrap_or_else(|e| e.into_inner());
+    *stored = args.into_iter().map(Into::into).collect();
+}
+
+fn with_args<R>(f: impl FnOnce(&[BString]) -> R) -> R {
+    let args = ARGS.read().unwrap_or_else(|e| e.into_inner());
+    f(&args)
+}
+
+pub fn get_arg_count() -> usize {
+    with_args(|args| args.len())
+}
+
+/// The argument at `index`, or `default` if there aren't that many.
+pub fn get_arg(index: usize, default: &str) -> BString {
+    with_args(|args| args.get(index).cloned().unwrap_or_else(|| default.into()))
+}
+
+/// Every argument joined by spaces.
+pub fn get_full() -> BString {
+    with_args(|args| args.join(" "))
+}
+
+pub fn has_switch(name: &str) -> bool {
+    with_args(|args| args.iter().any(|a| a == name))
+}
+
+/// The argument following `name`, or `default` if the switch isn't there or
+/// has nothing after it.
+pub fn get_switch(name: &str, default: &str) -> BString {
+    with_args(|args| {
+        args.iter()
+            .position(|a| a == name)
+            .and_then(|i| args.get(i + 1))
+            .cloned()
+            .unwrap_or_else(|| default.into())
+    })
+}
+
+/// The value of every occurrence of a switch that can be given more than
+/// once, in order.
+pub fn get_switches(name: &str) -> Vec<BString> {
+    with_args(|args| {
+        args.iter()
+            .zip(args.iter().skip(1))
+            .filter(|(a, _)| *a == name)
+            .map(|(_, value)| value.clone())
+            .collect()
+    })
+}
+
+/// The arguments that aren't switches, in order. The switches named in
+/// `with_value` take a value, so the argument after them is left out too.
+/// A lone `-` is an argument, not a switch.
+pub fn get_plain_args(with_value: &[&str]) -> Vec<BString> {
+    with_args(|args| {
+        let mut plain = Vec::new();
+        let mut iter = args.iter();
+        while let Some(arg) = iter.next() {
+            if arg == "-" || !arg.starts_with('-') {
+                plain.push(arg.clone());
+            } else if with_value.contains(&arg.as_str()) {
+                iter.next();
+            }
+        }
+        plain
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_switches() {
+        set(["extract", "-file", "addon.gma", "-warninvalid"]);
+        assert_eq!(get_arg_count(), 4);
+        assert_eq!(get_arg(0, ""), "extract");
+        assert_eq!(get_arg(9, "none"), "none");
+        assert_eq!(get_switch("-file", ""), "addon.gma");
+        assert_eq!(get_switch("-out", "default"), "default");
+        assert_eq!(get_switch("-warninvalid", "x"), "x");
+        assert!(has_switch("-warninvalid"));
+        assert!(!has_switch("-nocrc"));
+        assert_eq!(get_full(), "extract -file addon.gma -warninvalid");
+
+        set([
+            "extract", "-include", "lua/*", "-exclude", "*.txt", "-include", "*.vmt", "-include",
+        ]);
+        assert_eq!(get_switches("-include"), ["lua/*", "*.vmt"]);
+        assert_eq!(get_switches("-exclude"), ["*.txt"]);
+        assert!(get_switches("-out").is_empty());
+
+        set([
+            "cat",
+            "-file",
+            "addon.gma",
+            "-json",
+            "lua/a.lua",
+            "-",
+            "-format",
+            "json",
+        ]);
+        assert_eq!(
+            get_plain_args(&["-file", "-format"]),
+            ["cat", "lua/a.lua", "-"]
+        );
+        assert_eq!(
+            get_plain_args(&[]),
+            ["cat", "addon.gma", "lua/a.lua", "-", "json"]
+        );
+    }
+}
@@ -0,0 +1,65 @@
+//! Coloured console text using ANSI escape codes.
+//!
+//! Colours are kept on a stack so nested code can change the colour and put
+//! back whatever was there before. Nothing is emitted when stdout isn't a
+//! terminal, so piped output stays clean.
+
+use std::io::{self, IsTerminal, Write};
+use std::sync::Mutex;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Color {
+    Black,
+    Red,
+    Green,
+    Yellow,
+    Blue,
+    Magenta,
+    Cyan,
+    White,
+    Grey,
+}
+
+impl Color {
+    fn ansi_code(self) -> &'static str {
+        match self {
+            Color::Black => "\x1b[30m",
+            Color::Red => "\x1b[91m",
+            Color::Green => "\x1b[92m",
+            Color::Yellow => "\x1b[93m",
+            Color::Blue => "\x1b[94m",
+            Color::Magenta => "\x1b[95m",
+            Color::Cyan => "\x1b[96m",
+            Color::White => "\x1b[97m",
+            Color::Grey => "\x1b[90m",
+        }
+    }
+}
+
+const RESET: &str = "\x1b[0m";
+
+static STACK: Mutex<Vec<Color>> = Mutex::new(Vec::new());
+
+fn apply(color: Option<Color>) {
+    let mut stdout = io::stdout();
+    if !stdout.is_terminal() {
+        return;
+    }
+    let code = color.map(Color::ansi_code).unwrap_or(RESET);
+    let _ = stdout.write_all(code.as_bytes());
+    let _ = stdout.flush();
+}
+
+/// Changes the foreground colour until the matching `fg_color_pop`.
+pub fn fg_color_push(color: Color) {
+    let mut stack = STACK.lock().unwrap_or_else(|e| e.into_inner());
+    stack.push(color);
+    apply(Some(color));
+}
+
+/// Restores the colour that was active before the last `fg_color_push`.
+pub fn fg_color_pop() {
+    let mut stack = STACK.lock().unwrap_or_else(|e| e.into_inner());
+    stack.pop();
+    apply(stack.last().copied());
+}
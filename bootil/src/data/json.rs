@@ -0,0 +1,415 @@
+//! JSON import and export for `Tree`.
+//!
+//! Pretty output follows the layout of cJSON, which the C++ library uses:
+//! object members are indented with tabs and separated from their key by a
+//! tab, while lists stay on one line.
+
+use crate::bstring::BString;
+use crate::data::tree::{Kind, Tree};
+
+/// Objects and lists nested deeper than this are rejected, so hostile input
+/// can't overflow the stack.
+pub const MAX_DEPTH: usize = 128;
+
+/// Parses `json` into `tree`, replacing its children. The top level must be
+/// an object or a list. Returns false if the text isn't valid JSON, or is
+/// nested deeper than `MAX_DEPTH`.
+pub fn import(tree: &mut Tree, json: &str) -> bool {
+    let mut parser = Parser {
+        src: json.as_bytes(),
+        pos: 0,
+    };
+
+    parser.skip_whitespace();
+    let mut root = Tree::new();
+    let ok = match parser.peek() {
+        Some(b'{') => parser.parse_object(&mut root, 1),
+        Some(b'[') => parser.parse_array(&mut root, 1),
+        _ => false,
+    };
+
+    parser.skip_whitespace();
+    if !ok || parser.pos != parser.src.len() {
+        return false;
+    }
+
+    root.set_name(tree.name());
+    *tree = root;
+    true
+}
+
+/// Writes `tree` as JSON into `out`, replacing its contents.
+pub fn export(tree: &Tree, out: &mut BString, pretty: bool) {
+    out.clear();
+    if tree.is_array() {
+        write_array(tree, out, pretty, 1);
+    } else {
+        write_object(tree, out, pretty, 1);
+    }
+}
+
+fn write_value(tree: &Tree, out: &mut BString, pretty: bool, depth: usize) {
+    if tree.is_branch() {
+        if tree.is_array() {
+            write_array(tree, out, pretty, depth);
+        } else {
+            write_object(tree, out, pretty, depth);
+        }
+        return;
+    }
+
+    match tree.kind() {
+        Kind::Number | Kind::Bool => out.push_str(tree.value()),
+        Kind::Null => out.push_str("null"),
+        _ => write_string(tree.value(), out),
+    }
+}
+
+fn write_object(tree: &Tree, out: &mut BString, pretty: bool, depth: usize) {
+    out.push('{');
+    if pretty {
+        out.push('\n');
+    }
+
+    let count = tree.children().len();
+    for (i, child) in tree.children().iter().enumerate() {
+        if pretty {
+            push_tabs(out, depth);
+        }
+        write_string(child.name(), out);
+        out.push(':');
+        if pretty {
+            out.push('\t');
+        }
+        write_value(child, out, pretty, depth + 1);
+        if i + 1 != count {
+            out.push(',');
+        }
+        if pretty {
+            out.push('\n');
+        }
+    }
+
+    if pretty {
+        push_tabs(out, depth - 1);
+    }
+    out.push('}');
+}
+
+fn write_array(tree: &Tree, out: &mut BString, pretty: bool, depth: usize) {
+    out.push('[');
+
+    let count = tree.children().len();
+    for (i, child) in tree.children().iter().enumerate() {
+        write_value(child, out, pretty, depth + 1);
+        if i + 1 != count {
+            out.push(',');
+            if pretty {
+                out.push(' ');
+            }
+        }
+    }
+
+    out.push(']');
+}
+
+fn push_tabs(out: &mut BString, count: usize) {
+    for _ in 0..count {
+        out.push('\t');
+    }
+}
+
+fn write_string(value: &str, out: &mut BString) {
+    out.push('"');
+    for c in value.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\u{8}' => out.push_str("\\b"),
+            '\u{c}' => out.push_str("\\f"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            c if (c as u32) < 0x20 => out.push_str(&format!("\\u{:04x}", c as u32)),
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+}
+
+struct Parser<'a> {
+    src: &'a [u8],
+    pos: usize,
+}
+
+impl Parser<'_> {
+    fn peek(&self) -> Option<u8> {
+        self.src.get(self.pos).copied()
+    }
+
+    fn skip_whitespace(&mut self) {
+        while matches!(self.peek(), Some(b' ' | b'\t' | b'\r' | b'\n')) {
+            self.pos += 1;
+        }
+    }
+
+    fn eat(&mut self, expected: u8) -> bool {
+        self.skip_whitespace();
+        if self.peek() == Some(expected) {
+            self.pos += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn eat_literal(&mut self, literal: &str) -> bool {
+        if self.src[self.pos..].starts_with(literal.as_bytes()) {
+            self.pos += literal.len();
+            true
+        } else {
+            false
+        }
+    }
+
+    fn parse_object(&mut self, node: &mut Tree, depth: usize) -> bool {
+        node.set_kind(Kind::Object);
+        if !self.eat(b'{') {
+            return false;
+        }
+        if self.eat(b'}') {
+            return true;
+        }
+
+        loop {
+            self.skip_whitespace();
+            let Some(name) = self.parse_string() else {
+                return false;
+            };
+            if !self.eat(b':') {
+                return false;
+            }
+            let child = node.add_child(&name);
+            if !self.parse_value(child, depth) {
+                return false;
+            }
+            if self.eat(b',') {
+                continue;
+            }
+            return self.eat(b'}');
+        }
+    }
+
+    fn parse_array(&mut self, node: &mut Tree, depth: usize) -> bool {
+        node.set_kind(Kind::Array);
+        if !self.eat(b'[') {
+            return false;
+        }
+        if self.eat(b']') {
+            return true;
+        }
+
+        loop {
+            let child = node.add_child("");
+            if !self.parse_value(child, depth) {
+                return false;
+            }
+            if self.eat(b',') {
+                continue;
+            }
+            return self.eat(b']');
+        }
+    }
+
+    /// `depth` is how deeply nested the object or list holding the value is.
+    fn parse_value(&mut self, node: &mut Tree, depth: usize) -> bool {
+        self.skip_whitespace();
+        match self.peek() {
+            Some(b'{' | b'[') if depth >= MAX_DEPTH => false,
+            Some(b'{') => self.parse_object(node, depth + 1),
+            Some(b'[') => self.parse_array(node, depth + 1),
+            Some(b'"') => match self.parse_string() {
+                Some(value) => {
+                    node.set_value(&value);
+                    true
+                }
+                None => false,
+            },
+            Some(b't') if self.eat_literal("true") => {
+                node.set_bool(true);
+                true
+            }
+            Some(b'f') if self.eat_literal("false") => {
+                node.set_bool(false);
+                true
+            }
+            Some(b'n') if self.eat_literal("null") => {
+                node.set_null();
+                true
+            }
+            Some(b'-' | b'0'..=b'9') => self.parse_number(node),
+            _ => false,
+        }
+    }
+
+    fn parse_number(&mut self, node: &mut Tree) -> bool {
+        let start = self.pos;
+        while matches!(
+            self.peek(),
+            Some(b'-' | b'+' | b'.' | b'e' | b'E' | b'0'..=b'9')
+        ) {
+            self.pos += 1;
+        }
+        let text = std::str::from_utf8(&self.src[start..self.pos]).unwrap_or("");
+        if text.parse::<f64>().is_err() {
+            return false;
+        }
+        node.set_number(text);
+        true
+    }
+
+    fn parse_hex4(&mut self) -> Option<u32> {
+        let digits = self.src.get(self.pos..self.pos + 4)?;
+        let value = u32::from_str_radix(std::str::from_utf8(digits).ok()?, 16).ok()?;
+        self.pos += 4;
+        Some(value)
+    }
+
+    fn parse_string(&mut self) -> Option<BString> {
+        if self.peek() != Some(b'"') {
+            return None;
+        }
+        self.pos += 1;
+
+        let mut bytes = Vec::new();
+        loop {
+            let c = self.peek()?;
+            self.pos += 1;
+            match c {
+                b'"' => break,
+                b'\\' => {
+                    let escaped = self.peek()?;
+                    self.pos += 1;
+                    match escaped {
+                        b'"' => bytes.push(b'"'),
+                        b'\\' => bytes.push(b'\\'),
+                        b'/' => bytes.push(b'/'),
+                        b'b' => bytes.push(0x08),
+                        b'f' => bytes.push(0x0C),
+                        b'n' => bytes.push(b'\n'),
+                        b'r' => bytes.push(b'\r'),
+                        b't' => bytes.push(b'\t'),
+                        b'u' => {
+                            let mut code = self.parse_hex4()?;
+                            // Surrogate pair
+                            if (0xD800..0xDC00).contains(&code) && self.eat_literal("\\u") {
+                                let low = self.parse_hex4()?;
+                                code = 0x10000
+                                    + ((code - 0xD800) << 10)
+                                    + (low.wrapping_sub(0xDC00) & 0x3FF);
+                            }
+                            let c = char::from_u32(code).unwrap_or(char::REPLACEMENT_CHARACTER);
+                            bytes.extend_from_slice(c.encode_utf8(&mut [0; 4]).as_bytes());
+                        }
+                        _ => return None,
+                    }
+                }
+                c => bytes.push(c),
+            }
+        }
+
+        String::from_utf8(bytes).ok()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_import() {
+        let mut tree = Tree::new();
+        let json = r#"{
+            "title": "My \"Addon\"",
+            "type": "tool",
+            "tags": ["fun", "build"],
+            "ignore": [],
+            "count": 12,
+            "enabled": true,
+            "nothing": null,
+            "nested": { "unicode": "é😀" }
+        }"#;
+        assert!(import(&mut tree, json));
+        assert_eq!(tree.child_value("title", ""), "My \"Addon\"");
+        assert_eq!(tree.child_value("type", ""), "tool");
+        let tags: Vec<&str> = tree
+            .get_child("tags")
+            .unwrap()
+            .children()
+            .iter()
+            .map(|t| t.value())
+            .collect();
+        assert_eq!(tags, vec!["fun", "build"]);
+        assert!(tree.get_child("ignore").unwrap().is_array());
+        assert_eq!(tree.get_child("count").unwrap().kind(), Kind::Number);
+        assert_eq!(tree.child_value("enabled", ""), "true");
+        assert_eq!(tree.get_child("nothing").unwrap().kind(), Kind::Null);
+        assert_eq!(
+            tree.get_child("nested").unwrap().child_value("unicode", ""),
+            "\u{e9}\u{1F600}"
+        );
+    }
+
+    #[test]
+    fn test_import_invalid() {
+        let mut tree = Tree::new();
+        assert!(!import(&mut tree, ""));
+        assert!(!import(&mut tree, "\"just a string\""));
+        assert!(!import(&mut tree, "{\"a\": }"));
+        assert!(!import(&mut tree, "{\"a\": 1,}"));
+        assert!(!import(&mut tree, "{\"a\": 1} trailing"));
+        assert!(!import(&mut tree, "[1, 2"));
+    }
+
+    #[test]
+    fn test_import_depth() {
+        let mut tree = Tree::new();
+        let nested = |depth| "[".repeat(depth) + &"]".repeat(depth);
+        assert!(import(&mut tree, &nested(MAX_DEPTH)));
+        assert!(!import(&mut tree, &nested(MAX_DEPTH + 1)));
+        assert!(!import(&mut tree, &"[".repeat(200_000)));
+        assert!(!import(&mut tree, &"{\"a\":".repeat(200_000)));
+    }
+
+    #[test]
+    fn test_export_pretty() {
+        let mut tree = Tree::new();
+        tree.set_child("description", "Line one\nLine \"two\"");
+        tree.set_child("type", "tool");
+        let tags = tree.add_array("tags");
+        tags.add_child("").set_value("fun");
+        tags.add_child("").set_value("build");
+        tree.add_array("empty");
+        tree.add_child("nested").set_child_number("size", 5);
+
+        let mut out = BString::new();
+        export(&tree, &mut out, true);
+        assert_eq!(
+            out,
+            "{\n\t\"description\":\t\"Line one\\nLine \\\"two\\\"\",\n\t\"type\":\t\"tool\",\n\t\"tags\":\t[\"fun\", \"build\"],\n\t\"empty\":\t[],\n\t\"nested\":\t{\n\t\t\"size\":\t5\n\t}\n}"
+        );
+    }
+
+    #[test]
+    fn test_export_compact_round_trip() {
+        let mut tree = Tree::new();
+        assert!(import(
+            &mut tree,
+            r#"{"a":"b","list":[1,true,null,{"x":"y"}],"obj":{}}"#
+        ));
+
+        let mut out = BString::new();
+        export(&tree, &mut out, false);
+        assert_eq!(out, r#"{"a":"b","list":[1,true,null,{"x":"y"}],"obj":{}}"#);
+    }
+}
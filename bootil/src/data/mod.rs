@@ -0,0 +1,4 @@
+//! Structured data: a generic tree and a JSON importer/exporter for it.
+
+pub mod json;
+pub mod tree;
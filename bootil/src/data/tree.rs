@@ -0,0 +1,188 @@
+use crate::bstring::BString;
+
+/// What a node's value represents. Values are always stored as text; the
+/// kind only matters when the tree is exported.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Kind {
+    #[default]
+    String,
+    Number,
+    Bool,
+    Null,
+    /// A branch whose children are unnamed list items.
+    Array,
+    /// A branch whose children are named.
+    Object,
+}
+
+/// A named node holding either a value or a list of child nodes.
+///
+/// Children keep their insertion order. Nameless children make up a list.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Tree {
+    name: BString,
+    value: BString,
+    kind: Kind,
+    children: Vec<Tree>,
+}
+
+impl Tree {
+    pub fn new() -> Tree {
+        Tree::default()
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn set_name(&mut self, name: &str) {
+        self.name = name.into();
+    }
+
+    pub fn value(&self) -> &str {
+        &self.value
+    }
+
+    pub fn kind(&self) -> Kind {
+        self.kind
+    }
+
+    pub fn set_value(&mut self, value: &str) -> &mut Tree {
+        self.value = value.into();
+        self.kind = Kind::String;
+        self
+    }
+
+    pub fn set_number<T: std::fmt::Display>(&mut self, value: T) -> &mut Tree {
+        self.value = value.to_string();
+        self.kind = Kind::Number;
+        self
+    }
+
+    pub fn set_bool(&mut self, value: bool) -> &mut Tree {
+        self.value = value.to_string();
+        self.kind = Kind::Bool;
+        self
+    }
+
+    pub fn set_null(&mut self) -> &mut Tree {
+        self.value.clear();
+        self.kind = Kind::Null;
+        self
+    }
+
+    pub(crate) fn set_kind(&mut self, kind: Kind) {
+        self.kind = kind;
+    }
+
+    pub fn children(&self) -> &[Tree] {
+        &self.children
+    }
+
+    pub fn children_mut(&mut self) -> &mut Vec<Tree> {
+        &mut self.children
+    }
+
+    pub fn has_children(&self) -> bool {
+        !self.children.is_empty()
+    }
+
+    /// True for a list - either explicitly created as one, or a branch where
+    /// none of the children have names.
+    pub fn is_array(&self) -> bool {
+        match self.kind {
+            Kind::Array => true,
+            Kind::Object => false,
+            _ => self.has_children() && self.children.iter().all(|c| c.name.is_empty()),
+        }
+    }
+
+    /// True for anything that holds children rather than a value.
+    pub fn is_branch(&self) -> bool {
+        self.has_children() || matches!(self.kind, Kind::Array | Kind::Object)
+    }
+
+    pub fn get_child(&self, name: &str) -> Option<&Tree> {
+        self.children.iter().find(|c| c.name == name)
+    }
+
+    pub fn get_child_mut(&mut self, name: &str) -> Option<&mut Tree> {
+        self.children.iter_mut().find(|c| c.name == name)
+    }
+
+    /// Value of the named child, or `default` if there is no such child.
+    pub fn child_value(&self, name: &str, default: &str) -> BString {
+        self.get_child(name)
+            .map(|c| c.value.clone())
+            .unwrap_or_else(|| default.into())
+    }
+
+    /// Adds a child. Pass an empty name to add a list item.
+    pub fn add_child(&mut self, name: &str) -> &mut Tree {
+        self.children.push(Tree {
+            name: name.into(),
+            ..Tree::default()
+        });
+        self.children.last_mut().unwrap()
+    }
+
+    /// Adds a child that exports as a list even when it ends up empty.
+    pub fn add_array(&mut self, name: &str) -> &mut Tree {
+        let child = self.add_child(name);
+        child.kind = Kind::Array;
+        child
+    }
+
+    /// Adds a child that exports as an object even when it ends up empty.
+    pub fn add_object(&mut self, name: &str) -> &mut Tree {
+        let child = self.add_child(name);
+        child.kind = Kind::Object;
+        child
+    }
+
+    /// The named child, added if it doesn't exist yet.
+    pub fn get_or_add_child(&mut self, name: &str) -> &mut Tree {
+        match self.children.iter().position(|c| c.name == name) {
+            Some(i) => &mut self.children[i],
+            None => self.add_child(name),
+        }
+    }
+
+    /// Sets the string value of the named child, adding it if needed.
+    pub fn set_child(&mut self, name: &str, value: &str) -> &mut Tree {
+        self.get_or_add_child(name).set_value(value)
+    }
+
+    pub fn set_child_number<T: std::fmt::Display>(&mut self, name: &str, value: T) -> &mut Tree {
+        self.get_or_add_child(name).set_number(value)
+    }
+
+    pub fn set_child_bool(&mut self, name: &str, value: bool) -> &mut Tree {
+        self.get_or_add_child(name).set_bool(value)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_children() {
+        let mut tree = Tree::new();
+        tree.set_child("title", "Test");
+        tree.set_child("title", "Renamed");
+        tree.set_child_number("size", 12);
+        let tags = tree.add_child("tags");
+        tags.add_child("").set_value("fun");
+        tags.add_child("").set_value("build");
+
+        assert_eq!(tree.children().len(), 3);
+        assert_eq!(tree.child_value("title", ""), "Renamed");
+        assert_eq!(tree.get_child("size").unwrap().kind(), Kind::Number);
+        assert_eq!(tree.child_value("missing", "default"), "default");
+        assert!(tree.get_child("tags").unwrap().is_array());
+        assert!(!tree.is_array());
+        assert!(tree.add_array("empty").is_array());
+        assert!(!tree.add_child("leaf").is_branch());
+    }
+}
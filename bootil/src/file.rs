@@ -0,0 +1,172 @@
+//! Simple whole-file helpers. Like the C++ versions these report failure
+//! through their return value rather than an error.
+
+use std::fs;
+use std::io::Read;
+use std::path::Path;
+
+use crate::bstring::BString;
+use crate::buffer::Buffer;
+use crate::hasher::crc32::Crc32;
+
+/// Reads the whole file into `buffer`, replacing what was there. The
+/// buffer's position is left at 0.
+pub fn read(path: &str, buffer: &mut Buffer) -> bool {
+    match fs::read(path) {
+        Ok(data) => {
+            *buffer = Buffer::from_vec(data);
+            true
+        }
+        Err(_) => false,
+    }
+}
+
+/// Reads the whole file as text. Invalid UTF-8 is replaced rather than
+/// treated as an error.
+pub fn read_string(path: &str, out: &mut BString) -> bool {
+    match fs::read(path) {
+        Ok(data) => {
+            *out = String::from_utf8_lossy(&data).into_owned();
+            true
+        }
+        Err(_) => false,
+    }
+}
+
+/// Writes `data` to the file, replacing it if it exists.
+pub fn write(path: &str, data: &[u8]) -> bool {
+    fs::write(path, data).is_ok()
+}
+
+pub fn exists(path: &str) -> bool {
+    Path::new(path).exists()
+}
+
+pub fn is_folder(path: &str) -> bool {
+    Path::new(path).is_dir()
+}
+
+/// Size of the file in bytes, or -1 if it can't be read.
+pub fn size(path: &str) -> i64 {
+    fs::metadata(path).map(|m| m.len() as i64).unwrap_or(-1)
+}
+
+/// CRC32 of the file's contents, or 0 if it can't be read.
+pub fn crc(path: &str) -> u32 {
+    let Ok(mut file) = fs::File::open(path) else {
+        return 0;
+    };
+
+    let mut crc = Crc32::new();
+    let mut chunk = [0u8; 64 * 1024];
+    loop {
+        match file.read(&mut chunk) {
+            Ok(0) => break,
+            Ok(count) => crc.update(&chunk[..count]),
+            Err(_) => return 0,
+        }
+    }
+    crc.finalize()
+}
+
+/// Creates the folder, and its parents when `recursive` is set. Returns true
+/// if the folder exists afterwards.
+pub fn create_folder(path: &str, recursive: bool) -> bool {
+    let result = if recursive {
+        fs::create_dir_all(path)
+    } else {
+        fs::create_dir(path)
+    };
+    result.is_ok() || is_folder(path)
+}
+
+/// Appends the files in `folder` to `files`, as paths relative to `folder`
+/// using `/` as the separator. Returns the number of files added.
+pub fn get_files_in_folder(folder: &str, files: &mut Vec<BString>, recursive: bool) -> usize {
+    let before = files.len();
+    collect_files(Path::new(folder), "", files, recursive);
+    files.len() - before
+}
+
+fn collect_files(dir: &Path, prefix: &str, files: &mut Vec<BString>, recursive: bool) {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return;
+    };
+
+    for entry in entries.flatten() {
+        let name = entry.file_name().to_string_lossy().into_owned();
+        let Ok(file_type) = entry.file_type() else {
+            continue;
+        };
+
+        if file_type.is_dir() {
+            if recursive {
+                collect_files(
+                    &entry.path(),
+                    &format!("{}{}/", prefix, name),
+                    files,
+                    recursive,
+                );
+            }
+        } else {
+            files.push(format!("{}{}", prefix, name));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_folder(name: &str) -> String {
+        let path =
+            std::env::temp_dir().join(format!("bootil-file-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&path);
+        path.to_string_lossy().replace('\\', "/")
+    }
+
+    #[test]
+    fn test_write_read_size_crc() {
+        let folder = temp_folder("rw");
+        assert!(create_folder(&folder, true));
+
+        let path = format!("{}/test.txt", folder);
+        assert!(write(&path, b"123456789"));
+        assert!(exists(&path));
+        assert!(!is_folder(&path));
+        assert_eq!(size(&path), 9);
+        assert_eq!(crc(&path), 0xCBF4_3926);
+
+        let mut buffer = Buffer::new();
+        assert!(read(&path, &mut buffer));
+        assert_eq!(buffer.get_base(), b"123456789");
+
+        let mut text = BString::new();
+        assert!(read_string(&path, &mut text));
+        assert_eq!(text, "123456789");
+
+        assert_eq!(size(&format!("{}/missing", folder)), -1);
+        let _ = fs::remove_dir_all(&folder);
+    }
+
+    #[test]
+    fn test_get_files_in_folder() {
+        let folder = temp_folder("list");
+        assert!(create_folder(&format!("{}/lua/autorun", folder), true));
+        assert!(write(&format!("{}/addon.json", folder), b"{}"));
+        assert!(write(
+            &format!("{}/lua/autorun/init.lua", folder),
+            b"print()"
+        ));
+
+        let mut files = Vec::new();
+        assert_eq!(get_files_in_folder(&folder, &mut files, true), 2);
+        files.sort();
+        assert_eq!(files, vec!["addon.json", "lua/autorun/init.lua"]);
+
+        let mut top = Vec::new();
+        get_files_in_folder(&folder, &mut top, false);
+        assert_eq!(top, vec!["addon.json"]);
+        let _ = fs::remove_dir_all(&folder);
+    }
+}
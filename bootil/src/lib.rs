@@ -0,0 +1,21 @@
+//! Bootil - a small utility library used by gmad.
+//!
+//! This is a Rust take on Garry Newman's C++ Bootil library. It only covers
+//! the pieces gmad needs: byte strings, growable buffers, file helpers, a
+//! CRC32 hasher, command line parsing, coloured console output and a JSON
+//! capable data tree.
+
+pub mod bstring;
+pub mod buffer;
+pub mod command_line;
+pub mod console;
+pub mod data;
+pub mod file;
+pub mod hasher;
+pub mod output;
+pub mod string;
+pub mod time;
+
+pub use bstring::BString;
+pub use buffer::{AutoBuffer, BinaryRead, BinaryWrite, Buffer};
+pub use data::tree::Tree;
@@ -0,0 +1,107 @@
+//! Console output. Messages go to stdout, warnings are printed in yellow.
+//!
+//! Output can be captured instead, for tools that need to print something
+//! machine readable: messages are then dropped and warnings are kept until
+//! `take_warnings` is called. Or it can be sent to stderr, for tools that
+//! write data to stdout.
+
+use std::io::{self, Write};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+
+use crate::bstring::BString;
+use crate::console::{self, Color};
+
+static CAPTURED: Mutex<Option<Vec<BString>>> = Mutex::new(None);
+static TO_STDERR: AtomicBool = AtomicBool::new(false);
+
+/// Sends messages and warnings to stderr instead of stdout. Warnings aren't
+/// coloured while this is on.
+pub fn set_stderr(to_stderr: bool) {
+    TO_STDERR.store(to_stderr, Ordering::Relaxed);
+}
+
+/// Starts or stops capturing. Stopping throws away any captured warnings.
+pub fn set_capture(capture: bool) {
+    let mut captured = CAPTURED.lock().unwrap_or_else(|e| e.into_inner());
+    *captured = if capture { Some(Vec::new()) } else { None };
+}
+
+pub fn is_capturing() -> bool {
+    CAPTURED.lock().unwrap_or_else(|e| e.into_inner()).is_some()
+}
+
+/// The warnings captured so far, trimmed of surrounding whitespace.
+pub fn take_warnings() -> Vec<BString> {
+    let mut captured = CAPTURED.lock().unwrap_or_else(|e| e.into_inner());
+    captured.as_mut().map(std::mem::take).unwrap_or_default()
+}
+
+pub fn msg(text: &str) {
+    if is_capturing() {
+        return;
+    }
+
+    if TO_STDERR.load(Ordering::Relaxed) {
+        let _ = io::stderr().write_all(text.as_bytes());
+        return;
+    }
+
+    let mut stdout = io::stdout();
+    let _ = stdout.write_all(text.as_bytes());
+    let _ = stdout.flush();
+}
+
+pub fn warning(text: &str) {
+    {
+        let mut captured = CAPTURED.lock().unwrap_or_else(|e| e.into_inner());
+        if let Some(warnings) = captured.as_mut() {
+            warnings.push(text.trim().into());
+            return;
+        }
+    }
+
+    if TO_STDERR.load(Ordering::Relaxed) {
+        msg(text);
+        return;
+    }
+
+    console::fg_color_push(Color::Yellow);
+    msg(text);
+    console::fg_color_pop();
+}
+
+/// `output::msg` with `format!` style arguments.
+#[macro_export]
+macro_rules! msg {
+    ($($arg:tt)*) => {
+        $crate::output::msg(&format!($($arg)*))
+    };
+}
+
+/// `output::warning` with `format!` style arguments.
+#[macro_export]
+macro_rules! warning {
+    ($($arg:tt)*) => {
+        $crate::output::warning(&format!($($arg)*))
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_capture() {
+        set_capture(true);
+        assert!(is_capturing());
+        msg("dropped\n");
+        warning("\t\tfirst\n");
+        crate::warning!("second {}\n", 2);
+        assert_eq!(take_warnings(), vec!["first", "second 2"]);
+        assert!(take_warnings().is_empty());
+        set_capture(false);
+        assert!(!is_capturing());
+        assert!(take_warnings().is_empty());
+    }
+}
@@ -0,0 +1,71 @@
+//! Path manipulation on strings. Paths use `/` unless noted otherwise.
+
+use crate::bstring::BString;
+
+/// Extension without the dot, or an empty string if there isn't one.
+pub fn get_file_extension(path: &str) -> BString {
+    let name = get_extract_filename(path);
+    match name.rfind('.') {
+        Some(dot) => name[dot + 1..].to_string(),
+        None => BString::new(),
+    }
+}
+
+/// The path with the extension (and its dot) removed.
+pub fn get_strip_extension(path: &str) -> BString {
+    let mut out = path.to_string();
+    strip_extension(&mut out);
+    out
+}
+
+pub fn strip_extension(path: &mut BString) {
+    let name_start = path.rfind(['/', '\\']).map(|i| i + 1).unwrap_or(0);
+    if let Some(dot) = path[name_start..].rfind('.') {
+        path.truncate(name_start + dot);
+    }
+}
+
+/// The folder part of the path, including the trailing slash. Empty if the
+/// path has no folder.
+pub fn get_strip_filename(path: &str) -> BString {
+    match path.rfind(['/', '\\']) {
+        Some(slash) => path[..=slash].to_string(),
+        None => BString::new(),
+    }
+}
+
+/// The filename part of the path.
+pub fn get_extract_filename(path: &str) -> BString {
+    match path.rfind(['/', '\\']) {
+        Some(slash) => path[slash + 1..].to_string(),
+        None => path.to_string(),
+    }
+}
+
+/// Replaces every `from` with `to`.
+pub fn fix_slashes(path: &mut BString, from: &str, to: &str) {
+    *path = path.replace(from, to);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_paths() {
+        assert_eq!(get_file_extension("addons/test.gma"), "gma");
+        assert_eq!(get_file_extension("some.folder/noext"), "");
+        assert_eq!(get_strip_extension("addons/test.gma"), "addons/test");
+        assert_eq!(
+            get_strip_extension("some.folder/noext"),
+            "some.folder/noext"
+        );
+        assert_eq!(get_strip_filename("lua/autorun/init.lua"), "lua/autorun/");
+        assert_eq!(get_strip_filename("init.lua"), "");
+        assert_eq!(get_extract_filename("lua/autorun/init.lua"), "init.lua");
+
+        let mut path = BString::from("C:\\addons\\test");
+        fix_slashes(&mut path, "\\", "/");
+        assert_eq!(path, "C:/addons/test");
+    }
+}
@@ -0,0 +1,35 @@
+use crate::bstring::BString;
+
+/// Human readable size, such as "512 B" or "1.5 MB".
+pub fn memory(bytes: u64) -> BString {
+    let gb = bytes as f64 / 1_073_741_824.0;
+    if gb >= 1.0 {
+        return format!("{:.1} GB", gb);
+    }
+
+    let mb = bytes as f64 / 1_048_576.0;
+    if mb >= 1.0 {
+        return format!("{:.1} MB", mb);
+    }
+
+    let kb = bytes as f64 / 1024.0;
+    if kb >= 1.0 {
+        return format!("{:.1} KB", kb);
+    }
+
+    format!("{} B", bytes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_memory() {
+        assert_eq!(memory(0), "0 B");
+        assert_eq!(memory(1023), "1023 B");
+        assert_eq!(memory(1536), "1.5 KB");
+        assert_eq!(memory(5 * 1024 * 1024), "5.0 MB");
+        assert_eq!(memory(3 * 1024 * 1024 * 1024), "3.0 GB");
+    }
+}
@@ -0,0 +1,46 @@
+//! String helpers, grouped the same way as Bootil's `String` namespace.
+
+pub mod file;
+pub mod format;
+pub mod test;
+pub mod util;
+
+use crate::bstring::BString;
+
+/// ASCII lower-case copy of the string.
+pub fn get_lower(s: &str) -> BString {
+    s.to_ascii_lowercase()
+}
+
+/// ASCII upper-case copy of the string.
+pub fn get_upper(s: &str) -> BString {
+    s.to_ascii_uppercase()
+}
+
+/// Sorts the list alphabetically (by byte value), or in reverse.
+pub fn sort_list(list: &mut [BString], reverse: bool) {
+    list.sort();
+    if reverse {
+        list.reverse();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_case() {
+        assert_eq!(get_lower("Lua/AutoRun/Init.LUA"), "lua/autorun/init.lua");
+        assert_eq!(get_upper("gmad"), "GMAD");
+    }
+
+    #[test]
+    fn test_sort_list() {
+        let mut list: Vec<BString> = vec!["b".into(), "a".into(), "c".into()];
+        sort_list(&mut list, false);
+        assert_eq!(list, vec!["a", "b", "c"]);
+        sort_list(&mut list, true);
+        assert_eq!(list, vec!["c", "b", "a"]);
+    }
+}
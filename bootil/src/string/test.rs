@@ -0,0 +1,49 @@
+/// Matches `haystack` against a pattern where `*` matches any run of
+/// characters (including `/`) and `?` matches exactly one. Case sensitive.
+pub fn wildcard(pattern: &str, haystack: &str) -> bool {
+    let pattern = pattern.as_bytes();
+    let haystack = haystack.as_bytes();
+
+    let (mut p, mut h) = (0, 0);
+    let mut star: Option<(usize, usize)> = None;
+
+    while h < haystack.len() {
+        if p < pattern.len() && (pattern[p] == b'?' || pattern[p] == haystack[h]) {
+            p += 1;
+            h += 1;
+        } else if p < pattern.len() && pattern[p] == b'*' {
+            star = Some((p, h));
+            p += 1;
+        } else if let Some((star_p, star_h)) = star {
+            // Let the last star swallow one more character and retry
+            p = star_p + 1;
+            h = star_h + 1;
+            star = Some((star_p, star_h + 1));
+        } else {
+            return false;
+        }
+    }
+
+    pattern[p..].iter().all(|&c| c == b'*')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_wildcard() {
+        assert!(wildcard("lua/*.lua", "lua/autorun/init.lua"));
+        assert!(wildcard("*", ""));
+        assert!(wildcard("*thumbs.db", "materials/thumbs.db"));
+        assert!(wildcard("maps/?.bsp", "maps/a.bsp"));
+        assert!(wildcard(
+            "gamemodes/*/logo.png",
+            "gamemodes/sandbox/logo.png"
+        ));
+        assert!(!wildcard("lua/*.lua", "lua/autorun/init.txt"));
+        assert!(!wildcard("maps/?.bsp", "maps/ab.bsp"));
+        assert!(!wildcard("lua/*.lua", "LUA/init.lua"));
+        assert!(!wildcard("abc", "abcd"));
+    }
+}
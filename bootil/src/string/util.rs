@@ -0,0 +1,35 @@
+/// Removes any of the characters in `chars` from the end of the string.
+pub fn trim_right(s: &mut String, chars: &str) {
+    let len = s.trim_end_matches(|c| chars.contains(c)).len();
+    s.truncate(len);
+}
+
+/// Removes any of the characters in `chars` from the start of the string.
+pub fn trim_left(s: &mut String, chars: &str) {
+    let len = s.len() - s.trim_start_matches(|c| chars.contains(c)).len();
+    s.drain(..len);
+}
+
+/// Removes surrounding whitespace.
+pub fn trim(s: &mut String) {
+    trim_right(s, " \t\r\n");
+    trim_left(s, " \t\r\n");
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_trim() {
+        let mut s = String::from("//path/to///");
+        trim_right(&mut s, "/");
+        assert_eq!(s, "//path/to");
+        trim_left(&mut s, "/");
+        assert_eq!(s, "path/to");
+
+        let mut s = String::from(" \t text \n");
+        trim(&mut s);
+        assert_eq!(s, "text");
+    }
+}
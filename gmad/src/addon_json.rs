@@ -0,0 +1,167 @@
+use std::fs;
+
+use bootil::data::json;
+use bootil::data::tree::Tree;
+use bootil::string::get_lower;
+use bootil::string::test::wildcard;
+use bootil::BString;
+
+use crate::error::GmadError;
+use crate::format::tags;
+
+pub struct CAddonJson {
+    title: BString,
+    description: BString,
+    addon_type: BString,
+    ignores: Vec<BString>,
+    tags: Vec<BString>,
+    required_content: Vec<BString>,
+}
+
+impl CAddonJson {
+    pub fn new(str_info_file: &str) -> Result<CAddonJson, GmadError> {
+        let invalid = |reason: &str| GmadError::invalid_json(str_info_file, reason);
+
+        let mut addon_json = CAddonJson {
+            title: BString::new(),
+            description: BString::new(),
+            addon_type: BString::new(),
+            ignores: Vec::new(),
+            tags: Vec::new(),
+            required_content: Vec::new(),
+        };
+
+        let str_file_contents =
+            fs::read_to_string(str_info_file).map_err(|e| GmadError::io(str_info_file, e))?;
+
+        let mut tree = Tree::new();
+
+        if !json::import(&mut tree, &str_file_contents) {
+            return Err(invalid("Couldn't parse json"));
+        }
+
+        addon_json.title = tree.child_value("title", "");
+
+        if addon_json.title.is_empty() {
+            return Err(invalid("title is empty!"));
+        }
+
+        addon_json.description = tree.child_value("description", "Description");
+
+        addon_json.addon_type = get_lower(&tree.child_value("type", ""));
+
+        if addon_json.addon_type.is_empty() {
+            return Err(invalid("type is empty!"));
+        }
+
+        if !tags::type_exists(&addon_json.addon_type) {
+            return Err(invalid("type isn't a supported type!"));
+        }
+
+        if let Some(tag_list) = tree.get_child("tags") {
+            if tag_list.children().len() > 2 {
+                return Err(invalid("too many tags - specify 2 only!"));
+            }
+
+            for child in tag_list.children() {
+                let tag = get_lower(child.value());
+                if tag.is_empty() {
+                    continue;
+                }
+
+                if !tags::tag_exists(&tag) {
+                    return Err(invalid("tag isn't a supported word!"));
+                }
+
+                addon_json.tags.push(tag);
+            }
+        }
+
+        if let Some(ignores) = tree.get_child("ignore") {
+            for child in ignores.children() {
+                addon_json.ignores.push(child.value().into());
+            }
+        }
+
+        if let Some(required) = tree.get_child("requiredcontent") {
+            for child in required.children() {
+                // An empty string would end the list in the header
+                if child.value().is_empty() {
+                    continue;
+                }
+
+                if child.value().contains('\0') {
+                    return Err(invalid("required content can't contain NUL characters!"));
+                }
+
+                addon_json.required_content.push(child.value().into());
+            }
+        }
+
+        Ok(addon_json)
+    }
+
+    pub fn remove_ignored_files(&self, files: &mut Vec<BString>) {
+        files.retain(|f| {
+            if f == "addon.json" {
+                return false;
+            }
+
+            let str_low = get_lower(f);
+            if wildcard("*thumbs.db", &str_low) {
+                return false;
+            }
+            if wildcard("*desktop.ini", &str_low) {
+                return false;
+            }
+
+            if f == ".DS_Store" {
+                return false;
+            }
+            if wildcard("*/.DS_Store", f) {
+                return false;
+            }
+
+            !self.ignores.iter().any(|ignore| wildcard(ignore, f))
+        });
+    }
+
+    pub fn build_description(&self) -> BString {
+        build_description(self.get_description(), self.get_type(), &self.tags)
+    }
+
+    pub fn get_title(&self) -> &str {
+        &self.title
+    }
+
+    pub fn get_description(&self) -> &str {
+        &self.description
+    }
+
+    pub fn get_type(&self) -> &str {
+        &self.addon_type
+    }
+
+    pub fn get_tags(&self) -> &[BString] {
+        &self.tags
+    }
+
+    pub fn get_required_content(&self) -> &[BString] {
+        &self.required_content
+    }
+}
+
+/// The JSON that goes in an addon's description field, holding the actual
+/// description along with the type and tags.
+pub fn build_description(str_description: &str, str_type: &str, tags: &[BString]) -> BString {
+    let mut tree = Tree::new();
+    tree.set_child("description", str_description);
+    tree.set_child("type", str_type);
+    let tag_list = tree.add_array("tags");
+    for tag in tags {
+        tag_list.add_child("").set_value(tag);
+    }
+    let mut str_output = BString::new();
+    json::export(&tree, &mut str_output, true);
+    str_output
+}
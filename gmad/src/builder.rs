@@ -0,0 +1,354 @@
+use std::collections::HashMap;
+use std::io::{self, Read, Seek, SeekFrom, Write};
+
+use bootil::hasher::crc32::{self, Crc32};
+use bootil::string::get_lower;
+use bootil::BString;
+
+use crate::addon_json::build_description;
+use crate::error::GmadError;
+use crate::format::{tags, AddonHeader, FileEntry};
+use crate::whitelist;
+use crate::writer::Writer;
+
+/// Anything a file's contents can be streamed from.
+pub trait ReadSeek: Read + Seek {}
+
+impl<T: Read + Seek> ReadSeek for T {}
+
+enum Contents {
+    Bytes(Vec<u8>),
+    /// Read twice: once for the size and CRC, once to write it. `start` is
+    /// where the contents begin, taken the first time the addon is written so
+    /// every later write starts from the same place.
+    Stream {
+        input: Box<dyn ReadSeek>,
+        start: Option<u64>,
+    },
+}
+
+/// Builds an addon from files that don't have to exist on disk.
+///
+/// ```no_run
+/// # use std::fs::File;
+/// # use gmad::AddonBuilder;
+/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// let mut builder = AddonBuilder::new("My Addon", "tool").tags(["fun"]);
+/// builder.add_file("lua/autorun/init.lua", b"print()".to_vec());
+/// builder.write_to(File::create("my_addon.gma")?)?;
+/// # Ok(())
+/// # }
+/// ```
+pub struct AddonBuilder {
+    header: AddonHeader,
+    description: BString,
+    addon_type: BString,
+    tags: Vec<BString>,
+    files: Vec<(BString, Contents)>,
+    /// Where each name is in `files`.
+    by_name: HashMap<BString, usize>,
+}
+
+impl AddonBuilder {
+    pub fn new(title: &str, addon_type: &str) -> AddonBuilder {
+        AddonBuilder {
+            header: AddonHeader::new(title, ""),
+            description: "Description".into(),
+            addon_type: get_lower(addon_type),
+            tags: Vec::new(),
+            files: Vec::new(),
+            by_name: HashMap::new(),
+        }
+    }
+
+    pub fn description(mut self, description: &str) -> Self {
+        self.description = description.into();
+        self
+    }
+
+    /// At most two, from `tags::MISC`.
+    pub fn tags<I, S>(mut self, tags: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        self.tags = tags
+            .into_iter()
+            .map(|tag| get_lower(tag.as_ref()))
+            .collect();
+        self
+    }
+
+    pub fn author(mut self, author: &str) -> Self {
+        self.header.author = author.into();
+        self
+    }
+
+    pub fn steam_id(mut self, steam_id: u64) -> Self {
+        self.header.steam_id = steam_id;
+        self
+    }
+
+    /// Defaults to the time the builder was made.
+    pub fn timestamp(mut self, timestamp: u64) -> Self {
+        self.header.timestamp = timestamp;
+        self
+    }
+
+    pub fn addon_version(mut self, addon_version: i32) -> Self {
+        self.header.addon_version = addon_version;
+        self
+    }
+
+    pub fn required_content<I, S>(mut self, required_content: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<BString>,
+    {
+        self.header.required_content = required_content.into_iter().map(Into::into).collect();
+        self
+    }
+
+    /// Defaults to the current version. Version 1 can't hold required
+    /// content.
+    pub fn format_version(mut self, format_version: u8) -> Self {
+        self.header.format_version = format_version;
+        self
+    }
+
+    /// Adds a file from memory. Adding a name twice replaces the first one.
+    pub fn add_file(&mut self, str_name: &str, contents: Vec<u8>) -> &mut Self {
+        self.add(str_name, Contents::Bytes(contents))
+    }
+
+    /// Adds a file that's streamed from `input` when the addon is written,
+    /// starting from where `input` is the first time it's written. Writing
+    /// again reads the same contents.
+    pub fn add_reader<R: Read + Seek + 'static>(&mut self, str_name: &str, input: R) -> &mut Self {
+        self.add(
+            str_name,
+            Contents::Stream {
+                input: Box::new(input),
+                start: None,
+            },
+        )
+    }
+
+    fn add(&mut self, str_name: &str, contents: Contents) -> &mut Self {
+        // Stored the way gmad stores them: lower case, forward slashes
+        let str_name = get_lower(&str_name.replace('\\', "/"));
+        match self.by_name.get(&str_name) {
+            Some(&index) => self.files[index].1 = contents,
+            None => {
+                self.by_name.insert(str_name.clone(), self.files.len());
+                self.files.push((str_name, contents));
+            }
+        }
+        self
+    }
+
+    /// Checks the metadata and file names, then writes the addon to `out`.
+    /// Returns the number of bytes written.
+    pub fn write_to<W: Write>(&mut self, out: W) -> Result<u64, GmadError> {
+        self.validate()?;
+
+        let mut entries = Vec::with_capacity(self.files.len());
+        for (str_name, contents) in &mut self.files {
+            let (i_size, i_crc) = match contents {
+                Contents::Bytes(data) => (data.len() as i64, crc32::easy(data)),
+                Contents::Stream { input, start } => {
+                    measure(input.as_mut(), start).map_err(|e| GmadError::io(str_name, e))?
+                }
+            };
+
+            entries.push(FileEntry {
+                str_name: str_name.clone(),
+                i_size,
+                i_crc,
+                ..FileEntry::default()
+            });
+        }
+
+        let mut header = self.header.clone();
+        header.description = build_description(&self.description, &self.addon_type, &self.tags);
+
+        let mut writer = Writer::new(out);
+        writer.write_header(&header, &entries)?;
+        for ((_, contents), entry) in self.files.iter_mut().zip(&entries) {
+            match contents {
+                Contents::Bytes(data) => writer.write_contents(entry, &mut data.as_slice())?,
+                Contents::Stream { input, .. } => {
+                    writer.write_contents(entry, &mut input.take(entry.i_size as u64 + 1))?
+                }
+            }
+        }
+
+        let (_, written) = writer.finish(true)?;
+        Ok(written)
+    }
+
+    /// The same rules addon.json is held to, plus the whitelist.
+    fn validate(&self) -> Result<(), GmadError> {
+        let invalid = |reason: &str| Err(GmadError::InvalidMetadata(reason.into()));
+
+        if self.header.title.is_empty() {
+            return invalid("title is empty!");
+        }
+        if !tags::type_exists(&self.addon_type) {
+            return invalid("type isn't a supported type!");
+        }
+        if self.tags.len() > 2 {
+            return invalid("too many tags - specify 2 only!");
+        }
+        if !self.tags.iter().all(|tag| tags::tag_exists(tag)) {
+            return invalid("tag isn't a supported word!");
+        }
+        if self
+            .header
+            .required_content
+            .iter()
+            .any(|c| c.is_empty() || c.contains('\0'))
+        {
+            return invalid("required content can't be empty or contain NUL characters!");
+        }
+
+        if self.files.is_empty() {
+            return Err(GmadError::NoFiles);
+        }
+
+        let disallowed: Vec<BString> = self
+            .files
+            .iter()
+            .map(|(str_name, _)| str_name)
+            .filter(|str_name| !whitelist::check(str_name))
+            .cloned()
+            .collect();
+        if !disallowed.is_empty() {
+            return Err(GmadError::WhitelistViolation(disallowed));
+        }
+
+        Ok(())
+    }
+}
+
+/// The size and CRC of `input` from `start`, which is then rewound to it.
+/// An unset `start` is set to the current position.
+fn measure(input: &mut dyn ReadSeek, start: &mut Option<u64>) -> io::Result<(i64, u32)> {
+    let start = match *start {
+        Some(start) => input.seek(SeekFrom::Start(start))?,
+        None => *start.insert(input.stream_position()?),
+    };
+
+    let mut crc = Crc32::new();
+    let mut size: i64 = 0;
+    let mut chunk = [0u8; 64 * 1024];
+    loop {
+        match input.read(&mut chunk) {
+            Ok(0) => break,
+            Ok(count) => {
+                crc.update(&chunk[..count]);
+                size += count as i64;
+            }
+            Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+            Err(e) => return Err(e),
+        }
+    }
+
+    input.seek(SeekFrom::Start(start))?;
+    Ok((size, crc.finalize()))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use super::*;
+    use crate::reader::Reader;
+
+    #[test]
+    fn test_builder() {
+        let mut builder = AddonBuilder::new("Built", "Tool")
+            .description("Made in memory")
+            .tags(["fun", "build"])
+            .author("Pipeline")
+            .timestamp(1_700_000_000)
+            .required_content(["workshop/104691717"]);
+        builder.add_file("lua/autorun/init.lua", b"print()".to_vec());
+        builder.add_file("Materials\\A.vmt", b"old".to_vec());
+        builder.add_file("materials/a.vmt", b"x".to_vec());
+        let mut streamed = Cursor::new(b"skip-RIFF".to_vec());
+        streamed.set_position(5);
+        builder.add_reader("sound/a.wav", streamed);
+
+        let mut data = Vec::new();
+        let written = builder.write_to(&mut data).unwrap();
+        assert_eq!(written, data.len() as u64);
+
+        let mut reader = Reader::new(Cursor::new(data));
+        reader.parse().unwrap();
+        assert_eq!(reader.title(), "Built");
+        assert_eq!(reader.description(), "Made in memory");
+        assert_eq!(reader.addon_type(), "tool");
+        assert_eq!(reader.tags(), ["fun", "build"]);
+        assert_eq!(reader.author(), "Pipeline");
+        assert_eq!(reader.timestamp(), 1_700_000_000);
+        assert_eq!(reader.required_content(), ["workshop/104691717"]);
+        assert_eq!(reader.len(), 3);
+        let a_vmt = reader.find("materials/a.vmt").unwrap().i_file_number;
+        assert_eq!(reader.read_file_slice(a_vmt), Some(&b"x"[..]));
+        let a_wav = reader.find("sound/a.wav").unwrap().i_file_number;
+        assert_eq!(reader.read_file_slice(a_wav), Some(&b"RIFF"[..]));
+        assert!(reader.verify().unwrap().is_ok());
+    }
+
+    #[test]
+    fn test_builder_writes_twice() {
+        let mut builder = AddonBuilder::new("Built", "tool").timestamp(1_700_000_000);
+        builder.add_file("lua/a.lua", b"print()".to_vec());
+        let mut streamed = Cursor::new(b"skip-RIFF".to_vec());
+        streamed.set_position(5);
+        builder.add_reader("sound/a.wav", streamed);
+
+        let mut first = Vec::new();
+        builder.write_to(&mut first).unwrap();
+        let mut second = Vec::new();
+        builder.write_to(&mut second).unwrap();
+        assert_eq!(first, second);
+
+        let mut reader = Reader::new(Cursor::new(second));
+        reader.parse().unwrap();
+        let a_wav = reader.find("sound/a.wav").unwrap().i_file_number;
+        assert_eq!(reader.read_file_slice(a_wav), Some(&b"RIFF"[..]));
+    }
+
+    #[test]
+    fn test_builder_validation() {
+        let mut builder = AddonBuilder::new("Built", "tool");
+        assert!(matches!(
+            builder.write_to(Vec::new()),
+            Err(GmadError::NoFiles)
+        ));
+
+        builder.add_file("lua/a.lua", b"print()".to_vec());
+        builder.add_file("readme.txt", b"hi".to_vec());
+        match builder.write_to(Vec::new()) {
+            Err(GmadError::WhitelistViolation(files)) => assert_eq!(files, ["readme.txt"]),
+            other => panic!("expected a whitelist violation, got {:?}", other.err()),
+        }
+
+        for builder in [
+            AddonBuilder::new("", "tool"),
+            AddonBuilder::new("Built", "spreadsheet"),
+            AddonBuilder::new("Built", "tool").tags(["fun", "build", "comic"]),
+            AddonBuilder::new("Built", "tool").tags(["serious"]),
+            AddonBuilder::new("Built", "tool").required_content([""]),
+        ] {
+            let mut builder = builder;
+            builder.add_file("lua/a.lua", b"print()".to_vec());
+            assert!(matches!(
+                builder.write_to(Vec::new()),
+                Err(GmadError::InvalidMetadata(_))
+            ));
+        }
+    }
+}
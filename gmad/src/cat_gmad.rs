@@ -0,0 +1,44 @@
+use std::io::{self, Write};
+
+use bootil::{warning, BString};
+
+use crate::error::GmadError;
+use crate::reader::Reader;
+
+/// Writes a single entry to stdout, byte for byte. Everything else goes to
+/// stderr, so the output can be piped.
+pub fn cat_addon_file(str_file: BString, str_name: BString) -> i32 {
+    let mut addon = match Reader::open(&str_file) {
+        Ok(addon) => addon,
+        Err(err) => {
+            warning!("There was a problem opening the file: {}\n", err);
+            return 1;
+        }
+    };
+
+    if let Err(err) = addon.parse() {
+        warning!("There was a problem parsing the file: {}\n", err);
+        return 1;
+    }
+
+    let Some(entry) = addon.find(&str_name).cloned() else {
+        warning!("There is no \"{}\" in the addon\n", str_name);
+        return 1;
+    };
+
+    let stdout = io::stdout();
+    let mut out = stdout.lock();
+    let result = addon
+        .write_file(entry.i_file_number, &mut out)
+        .and_then(|_| out.flush().map_err(|e| GmadError::io("", e)));
+
+    match result {
+        Ok(()) => 0,
+        // Whatever we were piped into has seen enough, e.g. `head`
+        Err(GmadError::Io { source, .. }) if source.kind() == io::ErrorKind::BrokenPipe => 0,
+        Err(err) => {
+            warning!("Couldn't write \"{}\": {}\n", entry.str_name, err);
+            1
+        }
+    }
+}
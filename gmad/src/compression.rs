@@ -0,0 +1,209 @@
+//! Compressed addons. Workshop downloads and the game's cache keep addons
+//! LZMA compressed: the whole addon as a .lzma ("LZMA alone") stream,
+//! followed by `COMPRESSION_SIGNATURE`.
+
+use std::io::{self, Read, Write};
+
+use bootil::{BinaryRead, BinaryWrite};
+use lzma_rust2::{LzmaOptions, LzmaReader, LzmaWriter};
+
+use crate::error::GmadError;
+use crate::format::{COMPRESSION_SIGNATURE, IDENT};
+
+/// The stream header: a props byte, the dictionary size and the
+/// uncompressed size.
+pub const HEADER_SIZE: usize = 13;
+/// The LZMA preset used by `compress`, the same as the lzma tool's default.
+pub const PRESET: u32 = 6;
+
+/// The most `Reader::open` will decompress an addon to.
+pub const MAX_SIZE: u64 = 1024 * 1024 * 1024;
+/// The most memory, in KiB, the decoder may use. Preset 9 needs about 64 MiB,
+/// so this only turns away hostile dictionary sizes.
+pub const MEM_LIMIT_KB: u32 = 256 * 1024;
+
+/// Never reserve more than this up front, whatever size the header claims.
+const MAX_RESERVE: u64 = 256 * 1024 * 1024;
+
+struct StreamHeader {
+    props: u8,
+    dict_size: u32,
+    /// `u64::MAX` when the stream has an end marker instead.
+    size: u64,
+}
+
+fn read_header(mut data: &[u8]) -> io::Result<StreamHeader> {
+    Ok(StreamHeader {
+        props: data.read_u8()?,
+        dict_size: data.read_u32()?,
+        size: data.read_u64()?,
+    })
+}
+
+/// A decoder for the first `size` bytes. The dictionary is never bigger
+/// than that, whatever the header asks for.
+fn decoder(data: &[u8], size: u64) -> io::Result<LzmaReader<&[u8]>> {
+    let header = read_header(data)?;
+    LzmaReader::new_with_props(
+        &data[HEADER_SIZE..],
+        size.min(header.size),
+        header.props,
+        header.dict_size,
+        None,
+    )
+}
+
+fn corrupt(err: io::Error) -> GmadError {
+    GmadError::BadCompression(err.to_string())
+}
+
+/// Whether `start`, the beginning of a file, is a compressed addon. Only a
+/// few dozen bytes are needed: it's compressed if they decompress to "GMAD".
+pub fn is_compressed(start: &[u8]) -> bool {
+    if start.starts_with(IDENT) {
+        return false;
+    }
+
+    let mut ident = [0u8; 4];
+    decoder(start, IDENT.len() as u64)
+        .and_then(|mut reader| reader.read_exact(&mut ident))
+        .is_ok_and(|_| &ident == IDENT)
+}
+
+/// Decompresses a whole compressed addon, failing if it comes to more than
+/// `max_size` bytes. The signature after the stream is optional, so bare
+/// .lzma files work too.
+pub fn decompress(data: &[u8], max_size: u64) -> Result<Vec<u8>, GmadError> {
+    let too_big =
+        || GmadError::BadCompression(format!("the addon is bigger than {} bytes", max_size));
+
+    let header = read_header(data).map_err(corrupt)?;
+    if header.size != u64::MAX && header.size > max_size {
+        return Err(too_big());
+    }
+    let reader = LzmaReader::new_mem_limit(data, MEM_LIMIT_KB, None).map_err(corrupt)?;
+
+    // Streams with an end marker don't say how big they are, so the limit
+    // is checked as they're read
+    let mut addon = Vec::with_capacity(header.size.min(max_size).min(MAX_RESERVE) as usize);
+    reader
+        .take(max_size.saturating_add(1))
+        .read_to_end(&mut addon)
+        .map_err(corrupt)?;
+    if addon.len() as u64 > max_size {
+        return Err(too_big());
+    }
+
+    if header.size != u64::MAX && addon.len() as u64 != header.size {
+        return Err(GmadError::BadCompression(format!(
+            "expected {} bytes, got {}",
+            header.size,
+            addon.len()
+        )));
+    }
+
+    Ok(addon)
+}
+
+/// Compresses a whole addon, in the form `decompress` reads.
+pub fn compress(addon: &[u8]) -> Result<Vec<u8>, GmadError> {
+    let options = LzmaOptions::with_preset(PRESET);
+    let compress = || -> io::Result<Vec<u8>> {
+        let mut writer =
+            LzmaWriter::new_use_header(Vec::new(), &options, Some(addon.len() as u64))?;
+        writer.write_all(addon)?;
+        let mut out = writer.finish()?;
+        out.write_u32(COMPRESSION_SIGNATURE)?;
+        Ok(out)
+    };
+    compress().map_err(|e| GmadError::io("", e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::builder::AddonBuilder;
+    use crate::reader::Reader;
+    use std::io::Cursor;
+
+    fn example_addon() -> Vec<u8> {
+        let mut builder = AddonBuilder::new("Compressed", "tool");
+        builder.add_file("lua/autorun/init.lua", b"print('hello')\n".repeat(100));
+        let mut data = Vec::new();
+        builder.write_to(&mut data).unwrap();
+        data
+    }
+
+    #[test]
+    fn test_compression_round_trip() {
+        let addon = example_addon();
+        let compressed = compress(&addon).unwrap();
+        assert!(compressed.len() < addon.len());
+        assert!(compressed.ends_with(&COMPRESSION_SIGNATURE.to_le_bytes()));
+        assert!(is_compressed(&compressed[..64]));
+        assert!(!is_compressed(&addon));
+        assert_eq!(decompress(&compressed, MAX_SIZE).unwrap(), addon);
+
+        // Without the signature
+        assert_eq!(
+            decompress(&compressed[..compressed.len() - 4], MAX_SIZE).unwrap(),
+            addon
+        );
+
+        let mut reader = Reader::new(Cursor::new(decompress(&compressed, MAX_SIZE).unwrap()));
+        reader.parse().unwrap();
+        assert_eq!(reader.title(), "Compressed");
+    }
+
+    #[test]
+    fn test_decompression_limits() {
+        let addon = example_addon();
+        let compressed = compress(&addon).unwrap();
+        let max_size = addon.len() as u64 - 1;
+        assert!(matches!(
+            decompress(&compressed, max_size),
+            Err(GmadError::BadCompression(_))
+        ));
+
+        // No size in the header, just an end marker
+        let mut writer =
+            LzmaWriter::new_use_header(Vec::new(), &LzmaOptions::with_preset(PRESET), None)
+                .unwrap();
+        writer.write_all(&addon).unwrap();
+        let unsized_stream = writer.finish().unwrap();
+        assert_eq!(&unsized_stream[5..HEADER_SIZE], &[0xff; 8]);
+        assert_eq!(decompress(&unsized_stream, MAX_SIZE).unwrap(), addon);
+        assert!(matches!(
+            decompress(&unsized_stream, max_size),
+            Err(GmadError::BadCompression(_))
+        ));
+
+        // A dictionary far too big to be allocated
+        let mut hostile = compressed.clone();
+        hostile[1..5].copy_from_slice(&0xFFFF_0000u32.to_le_bytes());
+        assert!(matches!(
+            decompress(&hostile, MAX_SIZE),
+            Err(GmadError::BadCompression(_))
+        ));
+    }
+
+    #[test]
+    fn test_compression_errors() {
+        assert!(!is_compressed(b""));
+        assert!(!is_compressed(b"not an addon at all, just some text"));
+
+        // Compressed, but not an addon
+        let compressed = compress(b"this is not an addon").unwrap();
+        assert!(!is_compressed(&compressed));
+
+        let compressed = compress(&example_addon()).unwrap();
+        assert!(matches!(
+            decompress(&compressed[..compressed.len() / 2], MAX_SIZE),
+            Err(GmadError::BadCompression(_))
+        ));
+        assert!(matches!(
+            decompress(&compressed[..5], MAX_SIZE),
+            Err(GmadError::BadCompression(_))
+        ));
+    }
+}
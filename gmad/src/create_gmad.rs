@@ -1,194 +1,415 @@
-use bootil::{BString, Output, CommandLine, Time, AutoBuffer, File, Hasher, String};
+use std::cmp::Ordering;
+use std::fs::{self, File};
+use std::io::{self, BufWriter, Write};
 
-pub mod create_addon {
-    pub fn verify_files(files: &mut Vec<BString>, warn_invalid: bool) -> bool {
-        let mut b_ok = true;
+use bootil::{file, msg, string, warning, BString};
+
+use crate::addon_json::CAddonJson;
+use crate::compression;
+use crate::error::GmadError;
+use crate::format::{AddonHeader, VERSION};
+use crate::report::Report;
 
+pub mod create_addon {
+    use super::*;
+    use crate::format::FileEntry;
+    use crate::whitelist;
+    use crate::writer::Writer;
+
+    /// Drops files the whitelist doesn't allow. Unless `warn_invalid` is
+    /// set, any such file is an error.
+    pub fn verify_files(files: &mut Vec<BString>, warn_invalid: bool) -> Result<(), GmadError> {
         if files.is_empty() {
-            Output::Warning("No files found, can't continue!\n");
-            b_ok = false;
+            warning!("No files found, can't continue!\n");
+            return Err(GmadError::NoFiles);
         }
 
-        let mut old_files = std::mem::take(files);
-        files.clear();
+        let old_files = std::mem::take(files);
+        let mut disallowed = Vec::new();
 
-        for file in old_files.iter() {
-            Output::Msg("\t{}\n", file.as_str());
+        for file in old_files {
+            msg!("\t{}\n", file);
 
-            if Addon::WhiteList::Check(String::GetLower(file)) {
+            let str_lower = string::get_lower(&file);
+
+            if whitelist::check(&str_lower) {
                 files.push(file.clone());
             } else {
-                Output::Warning("\t\t[Not allowed by whitelist]\n");
-                if !warn_invalid {
-                    b_ok = false;
-                }
+                warning!("\t\t[Not allowed by whitelist]\n");
+                disallowed.push(file.clone());
             }
 
-            if String::GetLower(file) != *file {
-                Output::Warning("\t\t[Filename contains capital letters]\n");
+            if str_lower != file {
+                warning!("\t\t[Filename contains capital letters]\n");
             }
         }
 
-        b_ok
-    }
-
-    pub fn create(
-        buffer: &mut AutoBuffer,
-        str_folder: BString,
-        files: &Vec<BString>,
-        str_title: BString,
-        str_description: BString,
-    ) -> bool {
-        let do_crs = if CommandLine::HasSwitch("-nocrc") {
-            false
-        } else {
-            true
-        };
-
-        buffer.write(&Addon::Ident); // Ident (4)
-        buffer.write_type(Addon::Version as char); // Version (1)
-        buffer.write_type(0u64); // SteamID (8) [unused]
-        buffer.write_type(Time::UnixTimestamp() as u64); // TimeStamp (8)
-        buffer.write_type(0u8); // Required content (a list of strings)
-        buffer.write_string(str_title); // Addon Name (n)
-        buffer.write_string(str_description); // Addon Description (n)
-        buffer.write_string("Author Name"); // Addon Author (n) [unused]
-        buffer.write_type(1i32); // Addon Version (4) [unused]
-
-        Output::Msg("Writing file list...\n");
+        if !warn_invalid && !disallowed.is_empty() {
+            return Err(GmadError::WhitelistViolation(disallowed));
+        }
 
-        for (i, f) in files.iter().enumerate() {
-            let file_path = str_folder.clone() + f;
+        Ok(())
+    }
 
-            let i_size = File::Size(file_path.clone());
+    /// Writes the addon to `out`, streaming each file from disk. Without
+    /// `with_crc` the files and the addon are stored without CRCs. Returns
+    /// the number of bytes written.
+    pub fn create<W: Write>(
+        out: W,
+        str_folder: &str,
+        files: &[BString],
+        header: &AddonHeader,
+        with_crc: bool,
+    ) -> Result<u64, GmadError> {
+        let mut entries = Vec::with_capacity(files.len());
+        for f in files {
+            let file_path = format!("{}{}", str_folder, f);
+
+            let i_size = file::size(&file_path);
             if i_size <= 0 {
-                Output::Warning(
-                    "File '{}' seems to be empty, or we couldn't get its size! (errno={})\n",
-                    file_path,
-                    errno,
-                );
-                return false;
+                return Err(GmadError::EmptyFile(file_path));
             }
 
-            let i_file_num = i as u32 + 1;
-            buffer.write_type(i_file_num); // File number (4)
-            buffer.write_string(String::GetLower(f.clone())); // File name (all lower case!) (n)
-            buffer.write_type(i_size); // File size (8)
-
-            if do_crs {
-                let i_crc = File::CRC(file_path);
-                buffer.write_type(i_crc); // File CRC (4)
-            } else {
-                buffer.write_type(0u32);
-            }
+            entries.push(FileEntry {
+                str_name: string::get_lower(f), // File name (all lower case!)
+                i_size,
+                i_crc: if with_crc { file::crc(&file_path) } else { 0 },
+                ..FileEntry::default()
+            });
         }
 
-        let i_file_num = 0u32;
-        buffer.write_type(i_file_num);
-
-        Output::Msg("Writing files...\n");
+        msg!("Writing file list...\n");
 
-        for f in files.iter() {
-            let mut file_buffer = AutoBuffer::new();
-            let res = File::Read(str_folder.clone() + f, &mut file_buffer);
+        let mut writer = Writer::new(out);
+        writer.write_header(header, &entries)?;
 
-            if file_buffer.GetWritten() == 0 {
-                Output::Warning(
-                    "File '{}' seems to be empty (or we couldn't read it)\n",
-                    str_folder.clone() + f,
-                );
-                return false;
-            }
+        msg!("Writing files...\n");
 
-            let before = buffer.GetWritten();
-            buffer.write_buffer(&file_buffer);
-            let diff = buffer.GetWritten() - before;
-            if diff < 1 {
-                Output::Warning(
-                    "Failed to write file '{}' - written {} bytes! (Can't grow buffer?)\n",
-                    f,
-                    diff,
-                );
-                return false;
-            }
+        for (f, entry) in files.iter().zip(&entries) {
+            let file_path = format!("{}{}", str_folder, f);
+            let mut input = File::open(&file_path).map_err(|e| GmadError::io(&file_path, e))?;
+            writer.write_contents(entry, &mut input)?;
         }
 
-        if do_crs {
-            let addon_crc = Hasher::CRC32::Easy(buffer.GetBase(), buffer.GetWritten());
-            buffer.write_type(addon_crc);
-        } else {
-            buffer.write_type(0u32);
-        }
+        let (_, written) = writer.finish(with_crc)?;
+        Ok(written)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct CreateOptions {
+    /// Skip files the whitelist doesn't allow instead of failing.
+    pub warn_invalid: bool,
+    pub steam_id: u64,
+    /// Fixed timestamp for the header. Falls back to `SOURCE_DATE_EPOCH`,
+    /// then to the current time.
+    pub timestamp: Option<u64>,
+    pub addon_version: i32,
+    /// Produce byte-identical output for identical input: the timestamp is 0
+    /// unless set by `timestamp` or `SOURCE_DATE_EPOCH`.
+    pub reproducible: bool,
+    /// Write an older format, for tools that don't read the current one.
+    pub format_version: u8,
+    /// LZMA compress the addon, like the game's cache does.
+    pub compress: bool,
+    /// Store CRCs of the files and the addon. Skipping them is faster.
+    pub with_crc: bool,
+}
 
-        true
+impl Default for CreateOptions {
+    fn default() -> Self {
+        CreateOptions {
+            warn_invalid: false,
+            steam_id: 0,
+            timestamp: None,
+            addon_version: 1,
+            reproducible: false,
+            format_version: VERSION,
+            compress: false,
+            with_crc: true,
+        }
     }
 }
 
-pub fn create_addon_file(str_folder: BString, str_outfile: BString, warn_invalid: bool) -> i32 {
-    let mut b_errors = false;
+/// Puts files in the order gmad.exe finds them on Windows, so both produce
+/// the same addon: each folder's files come before its subfolders, and names
+/// are compared ignoring case.
+pub fn sort_files(files: &mut [BString]) {
+    files.sort_by(|a, b| {
+        let mut a_parts = a.split('/').peekable();
+        let mut b_parts = b.split('/').peekable();
+        loop {
+            match (a_parts.next(), b_parts.next()) {
+                (Some(a_part), Some(b_part)) => {
+                    // A file (the last part) sorts before a folder
+                    let a_is_file = a_parts.peek().is_none();
+                    let b_is_file = b_parts.peek().is_none();
+                    let order = b_is_file
+                        .cmp(&a_is_file)
+                        .then_with(|| string::get_upper(a_part).cmp(&string::get_upper(b_part)))
+                        .then_with(|| a_part.cmp(b_part));
+                    if order != Ordering::Equal {
+                        return order;
+                    }
+                }
+                (a_part, b_part) => return a_part.is_some().cmp(&b_part.is_some()),
+            }
+        }
+    });
+}
 
-    String::File::FixSlashes(&mut str_folder, "\\", "/");
-    String::Util::TrimRight(&mut str_folder, "/");
-    str_folder.push_str("/");
+/// The timestamp from the `SOURCE_DATE_EPOCH` environment variable, as
+/// described at https://reproducible-builds.org/specs/source-date-epoch/
+fn source_date_epoch() -> Result<Option<u64>, BString> {
+    match std::env::var("SOURCE_DATE_EPOCH") {
+        Ok(str_value) if !str_value.is_empty() => str_value
+            .trim()
+            .parse()
+            .map(Some)
+            .map_err(|_| format!("SOURCE_DATE_EPOCH must be a number, not \"{}\"", str_value)),
+        _ => Ok(None),
+    }
+}
 
-    if str_outfile.is_empty() {
-        str_outfile = str_folder.clone();
-        String::Util::TrimRight(&mut str_outfile, "/");
+pub fn create_addon_file(
+    mut str_folder: BString,
+    mut str_outfile: BString,
+    options: &CreateOptions,
+    json: bool,
+) -> i32 {
+    let mut report = Report::new(json, "create");
+
+    string::file::fix_slashes(&mut str_folder, "\\", "/");
+    string::util::trim_right(&mut str_folder, "/");
+    str_folder.push('/');
+
+    // "-" writes the addon to stdout
+    if str_outfile != "-" {
+        if str_outfile.is_empty() {
+            str_outfile = str_folder.clone();
+            string::util::trim_right(&mut str_outfile, "/");
+        }
+        string::file::strip_extension(&mut str_outfile);
+        str_outfile.push_str(".gma");
     }
-    String::File::StripExtension(&mut str_outfile);
-    str_outfile.push_str(".gma");
 
-    Output::Msg("Looking in folder \"{}\"\n", str_folder);
+    report.tree().set_child("folder", &str_folder);
+    report.tree().set_child("output", &str_outfile);
 
-    let addon_info = CAddonJson::new(str_folder.clone() + "addon.json");
-    if !addon_info.GetError().is_empty() {
-        Output::Warning(
-            "{} error: {}\n",
-            str_folder.clone() + "addon.json",
-            addon_info.GetError(),
-        );
-        return 1;
-    }
+    msg!("Looking in folder \"{}\"\n", str_folder);
+
+    let addon_info = match CAddonJson::new(&format!("{}addon.json", str_folder)) {
+        Ok(addon_info) => addon_info,
+        Err(err) => return report.fail(&err.to_string()),
+    };
 
     let mut files = Vec::new();
-    File::GetFilesInFolder(&str_folder, &mut files, true);
+    file::get_files_in_folder(&str_folder, &mut files, true);
 
-    addon_info.RemoveIgnoredFiles(&mut files);
-    String::SortList(&mut files, false);
+    addon_info.remove_ignored_files(&mut files);
 
-    if !create_addon::verify_files(&mut files, warn_invalid) {
-        Output::Warning("File list verification failed\n");
-        return 1;
-    }
+    // The folder listing comes back in whatever order the filesystem likes,
+    // sorting is what keeps the output the same from one run to the next
+    sort_files(&mut files);
 
-    let mut buffer = AutoBuffer::new();
-    if !create_addon::create(
-        &mut buffer,
-        str_folder.clone(),
-        &files,
-        addon_info.GetTitle(),
-        addon_info.BuildDescription(),
-    ) {
-        Output::Warning("Failed to create the addon\n");
-        return 1;
+    let found = files.clone();
+    if let Err(err) = create_addon::verify_files(&mut files, options.warn_invalid) {
+        return report.fail(&format!("File list verification failed: {}", err));
     }
 
-    Output::Msg(
-        "Writing the .gma...\n",
-        str_outfile,
-        String::Format::Memory(buffer.GetWritten()),
-    );
+    let mut header = AddonHeader::new(addon_info.get_title(), &addon_info.build_description());
+    header.steam_id = options.steam_id;
+    header.timestamp = match options.timestamp {
+        Some(timestamp) => timestamp,
+        None => match source_date_epoch() {
+            Ok(Some(timestamp)) => timestamp,
+            Ok(None) if options.reproducible => 0,
+            Ok(None) => header.timestamp,
+            Err(err) => return report.fail(&err),
+        },
+    };
+    header.addon_version = options.addon_version;
+    header.required_content = addon_info.get_required_content().to_vec();
+    header.format_version = options.format_version;
+
+    let write_addon = |out: &mut dyn Write| -> Result<u64, GmadError> {
+        if !options.compress {
+            return create_addon::create(out, &str_folder, &files, &header, options.with_crc);
+        }
 
-    if !File::Write(str_outfile.clone(), &buffer) {
-        Output::Warning("Couldn't save to file \"{}\"\n", str_outfile);
-        return 1;
-    }
+        // The compressed stream starts with the addon's size, so the whole
+        // addon has to be built first
+        let mut addon = Vec::new();
+        create_addon::create(&mut addon, &str_folder, &files, &header, options.with_crc)?;
+        msg!("Compressing...\n");
+        let compressed = compression::compress(&addon)?;
+        out.write_all(&compressed)
+            .and_then(|_| out.flush())
+            .map_err(|e| GmadError::io(&str_outfile, e))?;
+        Ok(compressed.len() as u64)
+    };
+
+    let result = if str_outfile == "-" {
+        write_addon(&mut BufWriter::new(io::stdout().lock()))
+    } else {
+        let output = match File::create(&str_outfile) {
+            Ok(output) => output,
+            Err(err) => {
+                return report.fail(&format!(
+                    "Couldn't save to file \"{}\": {}",
+                    str_outfile, err
+                ))
+            }
+        };
 
-    Output::Msg(
+        let result = write_addon(&mut BufWriter::new(output));
+        if result.is_err() {
+            // Don't leave half an addon behind
+            let _ = fs::remove_file(&str_outfile);
+        }
+        result
+    };
+
+    let written = match result {
+        Ok(written) => written,
+        Err(err) => return report.fail(&format!("Failed to create the addon: {}", err)),
+    };
+
+    msg!(
         "Successfully saved to \"{}\" [{}]\n",
         str_outfile,
-        String::Format::Memory(buffer.GetWritten()),
+        string::format::memory(written)
     );
 
-    return 0;
-}
\ No newline at end of file
+    if report.is_enabled() {
+        let tree = report.tree();
+        let file_list = tree.add_array("files");
+        for str_name in &files {
+            file_list.add_child("").set_value(str_name);
+        }
+        let skipped_list = tree.add_array("skipped");
+        for str_name in found.iter().filter(|f| !files.contains(f)) {
+            skipped_list.add_child("").set_value(str_name);
+        }
+        tree.set_child_number("size", written);
+        tree.set_child_number("timestamp", header.timestamp);
+        tree.set_child_bool("compressed", options.compress);
+    }
+
+    report.finish(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::reader::Reader;
+
+    #[test]
+    fn test_sort_files() {
+        let mut files: Vec<BString> = [
+            "materials/b.vmt",
+            "lua/zz.lua",
+            "materials/A.vmt",
+            "lua/aa/x.lua",
+            "lua/autorun.lua",
+            "lua/autorun/init.lua",
+            "lua/aa/b/c.lua",
+        ]
+        .map(Into::into)
+        .to_vec();
+        sort_files(&mut files);
+        assert_eq!(
+            files,
+            [
+                "lua/autorun.lua",
+                "lua/zz.lua",
+                "lua/aa/x.lua",
+                "lua/aa/b/c.lua",
+                "lua/autorun/init.lua",
+                "materials/A.vmt",
+                "materials/b.vmt",
+            ]
+        );
+    }
+
+    /// `tests/golden/golden.gma` was laid out by hand following
+    /// `CreateAddonFile` in Facepunch's gmad, for the folder next to it and a
+    /// timestamp of 1234567890. It's compared byte for byte.
+    #[test]
+    fn test_matches_original_gmad() {
+        let golden = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/golden/");
+        let out = std::env::temp_dir().join(format!("gmad-golden-{}.gma", std::process::id()));
+        let out = out.to_string_lossy().into_owned();
+
+        let options = CreateOptions {
+            timestamp: Some(1_234_567_890),
+            ..CreateOptions::default()
+        };
+        assert_eq!(
+            create_addon_file(format!("{}addon", golden), out.clone(), &options, false),
+            0
+        );
+
+        let created = fs::read(&out).unwrap();
+        let _ = fs::remove_file(&out);
+        let expected = fs::read(format!("{}golden.gma", golden)).unwrap();
+        assert!(
+            created == expected,
+            "output differs from tests/golden/golden.gma"
+        );
+    }
+
+    #[test]
+    fn test_reproducible_output() {
+        let root = std::env::temp_dir().join(format!("gmad-create-{}", std::process::id()));
+        let root = root.to_string_lossy().replace('\\', "/");
+        let folder = format!("{}/addon/", root);
+        file::create_folder(&format!("{}lua/autorun/client", folder), true);
+        file::create_folder(&format!("{}materials", folder), true);
+        file::write(
+            &format!("{}addon.json", folder),
+            br#"{"title": "Repro", "type": "tool", "tags": ["build"]}"#,
+        );
+        file::write(&format!("{}lua/autorun/init.lua", folder), b"print()");
+        file::write(&format!("{}lua/autorun/client/cl.lua", folder), b"print(1)");
+        file::write(&format!("{}materials/a.vmt", folder), b"x");
+
+        let options = CreateOptions {
+            reproducible: true,
+            ..CreateOptions::default()
+        };
+        let first = format!("{}/first.gma", root);
+        let second = format!("{}/second.gma", root);
+        assert_eq!(
+            create_addon_file(folder.clone(), first.clone(), &options, false),
+            0
+        );
+        assert_eq!(
+            create_addon_file(folder.clone(), second.clone(), &options, false),
+            0
+        );
+
+        let first = fs::read(first).unwrap();
+        assert_eq!(first, fs::read(second).unwrap());
+        // Timestamp is zeroed (unless SOURCE_DATE_EPOCH is set for the test run)
+        let expected = source_date_epoch().unwrap().unwrap_or(0);
+        assert_eq!(first[13..21], expected.to_le_bytes());
+
+        let options = CreateOptions {
+            with_crc: false,
+            ..options
+        };
+        let no_crc = format!("{}/no_crc.gma", root);
+        assert_eq!(
+            create_addon_file(folder.clone(), no_crc.clone(), &options, false),
+            0
+        );
+        let mut reader = Reader::new(io::Cursor::new(fs::read(no_crc).unwrap()));
+        reader.parse().unwrap();
+        let report = reader.verify().unwrap();
+        assert_eq!(report.unchecked.len(), 3);
+        assert_eq!(report.addon_crc, None);
+
+        let _ = fs::remove_dir_all(&root);
+    }
+}
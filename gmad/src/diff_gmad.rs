@@ -0,0 +1,352 @@
+use std::io::{Read, Seek};
+
+use bootil::hasher::crc32::Crc32;
+use bootil::{msg, string, BString, Buffer, Tree};
+
+use crate::error::GmadError;
+use crate::format::FileEntry;
+use crate::reader::{Input, Reader};
+use crate::report::Report;
+use crate::text_diff;
+
+/// Entries with these extensions get a text diff when asked for.
+pub const TEXT_EXTENSIONS: &[&str] = &["lua", "txt", "properties"];
+
+/// A header field that differs between the two addons.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MetadataChange {
+    pub field: &'static str,
+    pub old: BString,
+    pub new: BString,
+}
+
+/// An entry in both addons whose contents differ.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ModifiedEntry {
+    pub old: FileEntry,
+    pub new: FileEntry,
+}
+
+/// What changed from one addon to another.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct AddonDiff {
+    pub metadata: Vec<MetadataChange>,
+    /// In the new addon's index order.
+    pub added: Vec<FileEntry>,
+    /// The rest are in the old addon's index order.
+    pub removed: Vec<FileEntry>,
+    pub modified: Vec<ModifiedEntry>,
+    pub unchanged: usize,
+}
+
+impl AddonDiff {
+    pub fn is_empty(&self) -> bool {
+        self.metadata.is_empty()
+            && self.added.is_empty()
+            && self.removed.is_empty()
+            && self.modified.is_empty()
+    }
+}
+
+/// The CRC of an entry's contents: the stored one, or worked out from the
+/// contents if it was stored without one.
+fn entry_crc<R: Read + Seek>(addon: &mut Reader<R>, entry: &FileEntry) -> Result<u32, GmadError> {
+    if entry.i_crc != 0 {
+        return Ok(entry.i_crc);
+    }
+
+    let mut crc = Crc32::new();
+    addon.write_file(entry.i_file_number, &mut crc)?;
+    Ok(crc.finalize())
+}
+
+/// Compares two parsed addons. Entries are matched by name, and count as
+/// modified if their size or CRC differ.
+pub fn diff_addons<A, B>(old: &mut Reader<A>, new: &mut Reader<B>) -> Result<AddonDiff, GmadError>
+where
+    A: Read + Seek,
+    B: Read + Seek,
+{
+    let mut diff = AddonDiff::default();
+
+    let fields = [
+        ("title", old.title().to_string(), new.title().to_string()),
+        (
+            "type",
+            old.addon_type().to_string(),
+            new.addon_type().to_string(),
+        ),
+        ("tags", old.tags().join(", "), new.tags().join(", ")),
+        (
+            "description",
+            old.description().to_string(),
+            new.description().to_string(),
+        ),
+    ];
+    for (field, str_old, str_new) in fields {
+        if str_old != str_new {
+            diff.metadata.push(MetadataChange {
+                field,
+                old: str_old,
+                new: str_new,
+            });
+        }
+    }
+
+    for old_entry in old.get_list().to_vec() {
+        let Some(new_entry) = new.find(&old_entry.str_name).cloned() else {
+            diff.removed.push(old_entry);
+            continue;
+        };
+
+        let same = old_entry.i_size == new_entry.i_size
+            && entry_crc(old, &old_entry)? == entry_crc(new, &new_entry)?;
+        if same {
+            diff.unchanged += 1;
+        } else {
+            diff.modified.push(ModifiedEntry {
+                old: old_entry,
+                new: new_entry,
+            });
+        }
+    }
+
+    for new_entry in new.iter() {
+        if old.find(&new_entry.str_name).is_none() {
+            diff.added.push(new_entry.clone());
+        }
+    }
+
+    Ok(diff)
+}
+
+pub fn is_text_file(str_name: &str) -> bool {
+    TEXT_EXTENSIONS
+        .contains(&string::get_lower(&string::file::get_file_extension(str_name)).as_str())
+}
+
+/// A unified diff of a modified text entry.
+pub fn entry_text_diff<A, B>(
+    old: &mut Reader<A>,
+    new: &mut Reader<B>,
+    entry: &ModifiedEntry,
+) -> Result<BString, GmadError>
+where
+    A: Read + Seek,
+    B: Read + Seek,
+{
+    let mut old_buffer = Buffer::new();
+    old.read_file(entry.old.i_file_number, &mut old_buffer)?;
+    let mut new_buffer = Buffer::new();
+    new.read_file(entry.new.i_file_number, &mut new_buffer)?;
+
+    Ok(text_diff::unified_diff(
+        &format!("a/{}", entry.old.str_name),
+        &format!("b/{}", entry.new.str_name),
+        &String::from_utf8_lossy(old_buffer.get_base()),
+        &String::from_utf8_lossy(new_buffer.get_base()),
+    ))
+}
+
+fn open_addon(str_file: &str) -> Result<Reader<Input>, BString> {
+    let mut addon = Reader::open(str_file)
+        .map_err(|err| format!("There was a problem opening \"{}\": {}", str_file, err))?;
+    addon
+        .parse()
+        .map_err(|err| format!("There was a problem parsing \"{}\": {}", str_file, err))?;
+    Ok(addon)
+}
+
+fn entry_json(tree: &mut Tree, entry: &FileEntry) {
+    tree.set_child("name", &entry.str_name);
+    tree.set_child_number("size", entry.i_size);
+    tree.set_child("crc", &format!("{:08x}", entry.i_crc));
+}
+
+pub fn diff_addon_files(str_old: BString, str_new: BString, text: bool, json: bool) -> i32 {
+    let mut report = Report::new(json, "diff");
+    report.tree().set_child("old", &str_old);
+    report.tree().set_child("new", &str_new);
+
+    // stdin can only be read once
+    if str_old == "-" && str_new == "-" {
+        return report.fail("Only one of the addons can be read from stdin (-)");
+    }
+
+    msg!("Comparing \"{}\" with \"{}\"\n", str_old, str_new);
+
+    let (mut old, mut new) = match (open_addon(&str_old), open_addon(&str_new)) {
+        (Ok(old), Ok(new)) => (old, new),
+        (Err(err), _) | (_, Err(err)) => return report.fail(&err),
+    };
+
+    let diff = match diff_addons(&mut old, &mut new) {
+        Ok(diff) => diff,
+        Err(err) => {
+            return report.fail(&format!("There was a problem reading the addons: {}", err))
+        }
+    };
+
+    for change in &diff.metadata {
+        msg!(
+            "\t{}: \"{}\" -> \"{}\"\n",
+            change.field,
+            change.old.escape_debug(),
+            change.new.escape_debug()
+        );
+    }
+
+    for entry in &diff.removed {
+        msg!(
+            "\t- {} [{}]\n",
+            entry.str_name,
+            string::format::memory(entry.i_size as u64)
+        );
+    }
+
+    for entry in &diff.added {
+        msg!(
+            "\t+ {} [{}]\n",
+            entry.str_name,
+            string::format::memory(entry.i_size as u64)
+        );
+    }
+
+    let mut patches = Vec::new();
+    for entry in &diff.modified {
+        msg!(
+            "\tM {} [{} -> {}]\n",
+            entry.new.str_name,
+            string::format::memory(entry.old.i_size as u64),
+            string::format::memory(entry.new.i_size as u64)
+        );
+
+        let patch = if text && is_text_file(&entry.new.str_name) {
+            match entry_text_diff(&mut old, &mut new, entry) {
+                Ok(patch) => Some(patch),
+                Err(err) => {
+                    return report.fail(&format!("There was a problem reading the addons: {}", err))
+                }
+            }
+        } else {
+            None
+        };
+
+        if let Some(patch) = &patch {
+            msg!("{}", patch);
+        }
+        patches.push(patch);
+    }
+
+    msg!(
+        "{} added, {} removed, {} modified, {} unchanged\n",
+        diff.added.len(),
+        diff.removed.len(),
+        diff.modified.len(),
+        diff.unchanged
+    );
+
+    if report.is_enabled() {
+        let tree = report.tree();
+        let metadata_list = tree.add_array("metadata");
+        for change in &diff.metadata {
+            let item = metadata_list.add_object("");
+            item.set_child("field", change.field);
+            item.set_child("old", &change.old);
+            item.set_child("new", &change.new);
+        }
+        let added_list = tree.add_array("added");
+        for entry in &diff.added {
+            entry_json(added_list.add_object(""), entry);
+        }
+        let removed_list = tree.add_array("removed");
+        for entry in &diff.removed {
+            entry_json(removed_list.add_object(""), entry);
+        }
+        let modified_list = tree.add_array("modified");
+        for (entry, patch) in diff.modified.iter().zip(&patches) {
+            let item = modified_list.add_object("");
+            item.set_child("name", &entry.new.str_name);
+            item.set_child_number("old_size", entry.old.i_size);
+            item.set_child_number("new_size", entry.new.i_size);
+            item.set_child("old_crc", &format!("{:08x}", entry.old.i_crc));
+            item.set_child("new_crc", &format!("{:08x}", entry.new.i_crc));
+            if let Some(patch) = patch {
+                item.set_child("diff", patch);
+            }
+        }
+        tree.set_child_number("unchanged", diff.unchanged);
+        tree.set_child_bool("identical", diff.is_empty());
+    }
+
+    report.finish(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use super::*;
+    use crate::builder::AddonBuilder;
+
+    fn addon(builder: &mut AddonBuilder) -> Reader<Cursor<Vec<u8>>> {
+        let mut data = Vec::new();
+        builder.write_to(&mut data).unwrap();
+        let mut reader = Reader::new(Cursor::new(data));
+        reader.parse().unwrap();
+        reader
+    }
+
+    #[test]
+    fn test_diff_addons() {
+        let mut old_builder = AddonBuilder::new("Old", "tool").tags(["fun"]);
+        old_builder.add_file("lua/same.lua", b"same\n".to_vec());
+        old_builder.add_file("lua/changed.lua", b"print(1)\nprint(2)\n".to_vec());
+        old_builder.add_file("lua/removed.lua", b"gone\n".to_vec());
+        let mut old = addon(&mut old_builder);
+
+        let mut new_builder = AddonBuilder::new("New", "tool").tags(["fun"]);
+        new_builder.add_file("lua/same.lua", b"same\n".to_vec());
+        new_builder.add_file("lua/changed.lua", b"print(1)\nprint(3)\n".to_vec());
+        new_builder.add_file("lua/added.lua", b"new\n".to_vec());
+        let mut new = addon(&mut new_builder);
+
+        let diff = diff_addons(&mut old, &mut new).unwrap();
+        assert!(!diff.is_empty());
+        assert_eq!(
+            diff.metadata,
+            [MetadataChange {
+                field: "title",
+                old: "Old".into(),
+                new: "New".into()
+            }]
+        );
+        assert_eq!(diff.removed.len(), 1);
+        assert_eq!(diff.removed[0].str_name, "lua/removed.lua");
+        assert_eq!(diff.added.len(), 1);
+        assert_eq!(diff.added[0].str_name, "lua/added.lua");
+        assert_eq!(diff.modified.len(), 1);
+        assert_eq!(diff.modified[0].new.str_name, "lua/changed.lua");
+        assert_eq!(diff.unchanged, 1);
+
+        assert!(is_text_file(&diff.modified[0].new.str_name));
+        assert!(!is_text_file("materials/a.vmt"));
+        assert_eq!(
+            entry_text_diff(&mut old, &mut new, &diff.modified[0]).unwrap(),
+            "--- a/lua/changed.lua\n+++ b/lua/changed.lua\n@@ -1,2 +1,2 @@\n print(1)\n-print(2)\n+print(3)\n"
+        );
+
+        // Entries stored without a CRC are hashed from their contents
+        let mut unchecked = diff.modified[0].new.clone();
+        unchecked.i_crc = 0;
+        assert_eq!(
+            entry_crc(&mut new, &unchecked).unwrap(),
+            diff.modified[0].new.i_crc
+        );
+
+        let mut same = addon(&mut old_builder);
+        let diff = diff_addons(&mut old, &mut same).unwrap();
+        assert!(diff.is_empty());
+        assert_eq!(diff.unchanged, 3);
+    }
+}
@@ -0,0 +1,133 @@
+use std::error::Error;
+use std::fmt;
+use std::io;
+
+use bootil::BString;
+
+/// Everything that can go wrong reading, extracting or creating an addon.
+#[derive(Debug)]
+pub enum GmadError {
+    /// The file doesn't start with "GMAD".
+    BadIdent,
+    /// The format version is newer than this tool understands.
+    UnsupportedVersion(u8),
+    /// The file ends before the header does.
+    TruncatedHeader,
+    /// The file ends in the middle of the file index.
+    TruncatedIndex,
+    /// A string in the header or index is implausibly long.
+    StringTooLong,
+    /// An entry has a negative size.
+    InvalidEntrySize { name: BString, size: i64 },
+    /// An entry's contents run past the end of the file.
+    EntryPastEnd { name: BString },
+    /// The index has more entries than can be numbered.
+    TooManyFiles,
+    /// An entry name that would be written outside the output folder.
+    UnsafePath { name: BString, reason: BString },
+    /// There's no entry with this file number.
+    NoSuchFile(u32),
+    /// An entry's contents don't match the CRC stored in the index.
+    CrcMismatch {
+        name: BString,
+        expected: u32,
+        actual: u32,
+    },
+    /// addon.json couldn't be parsed or has invalid contents.
+    InvalidJson { path: BString, reason: BString },
+    /// The title, type or tags given to `AddonBuilder` aren't valid.
+    InvalidMetadata(BString),
+    /// Files that aren't allowed by the whitelist.
+    WhitelistViolation(Vec<BString>),
+    /// There were no files to put in the addon.
+    NoFiles,
+    /// A file to be added was empty or its size couldn't be read.
+    EmptyFile(BString),
+    /// A file's size changed between writing the index and its contents.
+    SizeChanged { name: BString, expected: i64 },
+    /// A compressed addon couldn't be decompressed.
+    BadCompression(BString),
+    /// Reading or writing failed. The path is empty when the data didn't come
+    /// from a named file.
+    Io { path: BString, source: io::Error },
+}
+
+impl GmadError {
+    pub fn io(path: &str, source: io::Error) -> GmadError {
+        GmadError::Io {
+            path: path.into(),
+            source,
+        }
+    }
+
+    pub fn invalid_json(path: &str, reason: &str) -> GmadError {
+        GmadError::InvalidJson {
+            path: path.into(),
+            reason: reason.into(),
+        }
+    }
+}
+
+impl fmt::Display for GmadError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            GmadError::BadIdent => write!(f, "not a GMA file (bad ident)"),
+            GmadError::UnsupportedVersion(version) => {
+                write!(f, "unsupported format version {}", version)
+            }
+            GmadError::TruncatedHeader => write!(f, "file ends in the middle of the header"),
+            GmadError::TruncatedIndex => write!(f, "file ends in the middle of the file index"),
+            GmadError::StringTooLong => write!(f, "string in the header or index is too long"),
+            GmadError::InvalidEntrySize { name, size } => {
+                write!(f, "'{}' has an invalid size ({})", name, size)
+            }
+            GmadError::EntryPastEnd { name } => {
+                write!(f, "'{}' runs past the end of the file", name)
+            }
+            GmadError::TooManyFiles => write!(f, "too many files in the index"),
+            GmadError::UnsafePath { name, reason } => {
+                write!(f, "'{}' {}", name.escape_debug(), reason)
+            }
+            GmadError::NoSuchFile(file_number) => {
+                write!(f, "there is no file number {} in the addon", file_number)
+            }
+            GmadError::CrcMismatch {
+                name,
+                expected,
+                actual,
+            } => write!(
+                f,
+                "'{}' is corrupt (CRC {:08x}, expected {:08x})",
+                name, actual, expected
+            ),
+            GmadError::InvalidJson { path, reason } => write!(f, "{} error: {}", path, reason),
+            GmadError::InvalidMetadata(reason) => write!(f, "invalid addon metadata: {}", reason),
+            GmadError::WhitelistViolation(files) => {
+                write!(f, "not allowed by whitelist: {}", files.join(", "))
+            }
+            GmadError::NoFiles => write!(f, "no files found"),
+            GmadError::EmptyFile(path) => {
+                write!(f, "'{}' seems to be empty, or we couldn't read it", path)
+            }
+            GmadError::SizeChanged { name, expected } => write!(
+                f,
+                "'{}' changed size while the addon was being written (expected {} bytes)",
+                name, expected
+            ),
+            GmadError::BadCompression(reason) => {
+                write!(f, "couldn't decompress the addon: {}", reason)
+            }
+            GmadError::Io { path, source } if path.is_empty() => write!(f, "{}", source),
+            GmadError::Io { path, source } => write!(f, "{}: {}", path, source),
+        }
+    }
+}
+
+impl Error for GmadError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            GmadError::Io { source, .. } => Some(source),
+            _ => None,
+        }
+    }
+}
@@ -1,61 +1,296 @@
-use bootil::{BString, Output, File, AutoBuffer, String};
+use std::fs::{self, File};
 
-pub fn extract_addon_file(str_file: BString, mut str_out_path: BString) -> i32 {
-    Output::Msg("Opening \"{}\"\n", str_file);
+use bootil::string::test::wildcard;
+use bootil::{file, msg, string, warning, BString};
+
+use crate::error::GmadError;
+use crate::reader::Reader;
+
+/// Turns an entry name into a relative path that stays inside the output
+/// folder. Backslashes become slashes and empty or `.` components are
+/// dropped; absolute paths, drive letters, `..` and NUL bytes are refused.
+pub fn safe_entry_path(str_name: &str) -> Result<BString, GmadError> {
+    let unsafe_path = |reason: &str| GmadError::UnsafePath {
+        name: str_name.into(),
+        reason: reason.into(),
+    };
+
+    if str_name.contains('\0') {
+        return Err(unsafe_path("contains a NUL byte"));
+    }
+
+    let str_path = str_name.replace('\\', "/");
+    if str_path.starts_with('/') {
+        return Err(unsafe_path("is an absolute path"));
+    }
+
+    let mut components = Vec::new();
+    for component in str_path.split('/') {
+        match component {
+            "" | "." => continue,
+            ".." => return Err(unsafe_path("leaves the output folder")),
+            // Drive letters, and alternate data streams on NTFS
+            c if c.contains(':') => return Err(unsafe_path("contains a drive letter or ':'")),
+            c => components.push(c),
+        }
+    }
+
+    if components.is_empty() {
+        return Err(unsafe_path("is empty"));
+    }
+
+    Ok(components.join("/"))
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct ExtractOptions {
+    /// Write entries with unsafe names under `badnames/` instead of skipping
+    /// them.
+    pub unsafe_to_badnames: bool,
+    /// Check every entry against its CRC and warn about mismatches.
+    pub check_crc: bool,
+    /// Delete entries that fail the CRC check. Implies `check_crc`.
+    pub skip_corrupt: bool,
+    /// Only extract entries matching one of these patterns. Everything is
+    /// extracted when it's empty.
+    pub include: Vec<BString>,
+    /// Never extract entries matching one of these patterns.
+    pub exclude: Vec<BString>,
+}
+
+impl ExtractOptions {
+    /// Whether the include and exclude patterns let this entry through.
+    /// Patterns use the whitelist's wildcard syntax and ignore case.
+    pub fn wants(&self, str_name: &str) -> bool {
+        let str_lower = string::get_lower(str_name);
+        let matches = |pattern: &BString| wildcard(&string::get_lower(pattern), &str_lower);
+
+        if !self.include.is_empty() && !self.include.iter().any(matches) {
+            return false;
+        }
+
+        !self.exclude.iter().any(matches)
+    }
+}
+
+/// Extracts every file in the addon. Entries with unsafe names are skipped
+/// unless the options say otherwise. Returns 1 if any file couldn't be
+/// written, or failed the CRC check when that was asked for.
+pub fn extract_addon_file(
+    str_file: BString,
+    mut str_out_path: BString,
+    options: &ExtractOptions,
+) -> i32 {
+    msg!("Opening \"{}\"\n", str_file);
 
     // If an output path hasn't been provided, make our own
     if str_out_path.is_empty() {
-        str_out_path = String::File::GetStripExtension(str_file.clone());
+        if str_file == "-" {
+            warning!("Extracting from stdin needs -out (the folder to extract to)\n");
+            return 1;
+        }
+        str_out_path = string::file::get_strip_extension(&str_file);
     }
 
     // Remove slash, add slash (enforces a slash)
-    String::File::FixSlashes(&mut str_out_path);
-    String::Util::TrimRight(&mut str_out_path, "/");
-    str_out_path.push_str("/");
+    string::file::fix_slashes(&mut str_out_path, "\\", "/");
+    string::util::trim_right(&mut str_out_path, "/");
+    str_out_path.push('/');
 
-    let mut addon = Addon::Reader::new();
-    if !addon.ReadFromFile(str_file.clone()) {
-        Output::Warning("There was a problem opening the file\n");
-        return 1;
-    }
+    let mut addon = match Reader::open(&str_file) {
+        Ok(addon) => addon,
+        Err(err) => {
+            warning!("There was a problem opening the file: {}\n", err);
+            return 1;
+        }
+    };
 
-    if !addon.Parse() {
-        Output::Warning("There was a problem parsing the file\n");
+    if let Err(err) = addon.parse() {
+        warning!("There was a problem parsing the file: {}\n", err);
         return 1;
     }
 
-    Output::Msg("Extracting Files:\n");
+    msg!("Extracting Files:\n");
     let mut bad_file_count = 0;
-    for entry in addon.GetList().iter() {
-        Output::Msg(
+    let mut unsafe_count = 0;
+    let mut corrupt_count = 0;
+    let mut failed_count = 0;
+    let mut filtered_count = 0;
+    let entries = addon.get_list().to_vec();
+    for entry in entries.iter() {
+        if !options.wants(&entry.str_name) {
+            filtered_count += 1;
+            continue;
+        }
+
+        msg!(
             "\t{} [{}]\n",
-            entry.strName,
-            String::Format::Memory(entry.iSize),
+            entry.str_name,
+            string::format::memory(entry.i_size as u64)
         );
 
-        // Make sure folders exist
-        File::CreateFolder(
-            &format!("{}{}", str_out_path, String::File::GetStripFilename(entry.strName)),
-            true,
-        );
+        let output = match safe_entry_path(&entry.str_name) {
+            Ok(str_name) => {
+                // Make sure folders exist
+                file::create_folder(
+                    &format!(
+                        "{}{}",
+                        str_out_path,
+                        string::file::get_strip_filename(&str_name)
+                    ),
+                    true,
+                );
+
+                let str_path = format!("{}{}", str_out_path, str_name);
+                match File::create(&str_path) {
+                    Ok(output) => Some((output, str_path)),
+                    Err(_) => {
+                        warning!(
+                            "\t\tCouldn't write, trying to write as 'badnames/{}.unk'..\n",
+                            bad_file_count
+                        );
+                        None
+                    }
+                }
+            }
+            Err(err) => {
+                warning!("\t\tSkipping: {}\n", err);
+                unsafe_count += 1;
+                if !options.unsafe_to_badnames {
+                    continue;
+                }
+                warning!("\t\tWriting as 'badnames/{}.unk'..\n", bad_file_count);
+                None
+            }
+        };
 
-        // Load the file into the buffer
-        let mut file_contents = AutoBuffer::new();
-        if addon.ReadFile(entry.iFileNumber, &mut file_contents) {
-            // Write the file to disk
-            if !File::Write(&format!("{}{}", str_out_path, entry.strName), &file_contents) {
-                let gen_path = format!("badnames/{}.unk", bad_file_count);
-                Output::Warning("\t\tCouldn't write, trying to write as '{}'..\n", gen_path);
+        // Stream the file straight to disk
+        let (mut output, str_path) = match output {
+            Some(output) => output,
+            None => {
+                let gen_path = format!("{}badnames/{}.unk", str_out_path, bad_file_count);
+                bad_file_count += 1;
 
                 // Try to write the file but don't use any of its name, since we don't know which part of it may have caused the problem
-                File::CreateFolder(&format!("{}badnames/", str_out_path), true);
-                File::Write(&format!("{}{}", str_out_path, gen_path), &file_contents);
-                bad_file_count += 1;
+                file::create_folder(&format!("{}badnames/", str_out_path), true);
+                match File::create(&gen_path) {
+                    Ok(output) => (output, gen_path),
+                    Err(_) => {
+                        warning!("\t\tCouldn't write '{}'\n", gen_path);
+                        failed_count += 1;
+                        continue;
+                    }
+                }
             }
+        };
+
+        let result = if options.check_crc || options.skip_corrupt {
+            addon.write_file_checked(entry.i_file_number, &mut output)
         } else {
-            Output::Warning("\t\tCouldn't extract!\n");
+            addon.write_file(entry.i_file_number, &mut output)
+        };
+
+        match result {
+            Ok(_) => {}
+            Err(err @ GmadError::CrcMismatch { .. }) => {
+                corrupt_count += 1;
+                if options.skip_corrupt {
+                    drop(output);
+                    let _ = fs::remove_file(&str_path);
+                    warning!("\t\tSkipping: {}\n", err);
+                } else {
+                    warning!("\t\tWarning: {}\n", err);
+                }
+            }
+            Err(err) => {
+                warning!("\t\tCouldn't extract: {}\n", err);
+                failed_count += 1;
+            }
+        }
+    }
+
+    if filtered_count > 0 {
+        msg!("{} file(s) left out by -include/-exclude\n", filtered_count);
+    }
+    if unsafe_count > 0 {
+        warning!("{} file(s) had unsafe names\n", unsafe_count);
+    }
+    if corrupt_count > 0 {
+        warning!("{} file(s) failed the CRC check\n", corrupt_count);
+    }
+    if failed_count > 0 {
+        warning!("{} file(s) couldn't be extracted\n", failed_count);
+    }
+    msg!("Done!\n");
+
+    if corrupt_count > 0 || failed_count > 0 {
+        1
+    } else {
+        0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_extract_filters() {
+        let everything = ExtractOptions::default();
+        assert!(everything.wants("sound/a.wav"));
+
+        let options = ExtractOptions {
+            include: vec!["lua/**".into(), "*.VMT".into()],
+            exclude: vec!["lua/autorun/server/*".into()],
+            ..ExtractOptions::default()
+        };
+        assert!(options.wants("lua/autorun/init.lua"));
+        assert!(options.wants("materials/models/a.vmt"));
+        assert!(!options.wants("lua/autorun/server/sv.lua"));
+        assert!(!options.wants("materials/models/a.vtf"));
+        assert!(!options.wants("lua"));
+
+        let options = ExtractOptions {
+            exclude: vec!["*.txt".into()],
+            ..ExtractOptions::default()
+        };
+        assert!(options.wants("data/a.dat"));
+        assert!(!options.wants("data/readme.txt"));
+    }
+
+    #[test]
+    fn test_safe_entry_path() {
+        assert_eq!(
+            safe_entry_path("lua/autorun/init.lua").unwrap(),
+            "lua/autorun/init.lua"
+        );
+        assert_eq!(
+            safe_entry_path("lua\\autorun//./init.lua").unwrap(),
+            "lua/autorun/init.lua"
+        );
+        assert_eq!(
+            safe_entry_path("materials/...vmt").unwrap(),
+            "materials/...vmt"
+        );
+
+        for bad in [
+            "../../.bashrc",
+            "lua/../../x.lua",
+            "..\\x.lua",
+            "/etc/passwd",
+            "\\\\server\\share\\x",
+            "C:/Windows/x.dll",
+            "c:x.lua",
+            "lua/init.lua:stream",
+            "lua/\0.lua",
+            "",
+            "./",
+        ] {
+            assert!(
+                matches!(safe_entry_path(bad), Err(GmadError::UnsafePath { .. })),
+                "{:?} should be refused",
+                bad
+            );
         }
     }
-    Output::Msg("Done!\n");
-    return 0;
-}
\ No newline at end of file
+}
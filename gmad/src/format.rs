@@ -0,0 +1,103 @@
+use bootil::BString;
+
+pub const IDENT: &[u8; 4] = b"GMAD";
+pub const VERSION: u8 = 3;
+/// The oldest format version that can be read or written.
+pub const MIN_VERSION: u8 = 1;
+pub const APP_ID: u32 = 4000;
+/// Follows the LZMA stream in a compressed addon, see `compression`.
+pub const COMPRESSION_SIGNATURE: u32 = 0xBEEFCACE;
+
+#[repr(C)]
+pub struct Header {
+    pub ident: [u8; 4],
+    pub version: u8,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct FileEntry {
+    pub str_name: BString,
+    pub i_size: i64,
+    pub i_crc: u32,
+    pub i_file_number: u32,
+    pub i_offset: i64,
+}
+
+pub type FileEntryList = Vec<FileEntry>;
+
+/// The header fields an addon is created with.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AddonHeader {
+    pub title: BString,
+    /// The JSON description, as built by `CAddonJson::build_description`.
+    pub description: BString,
+    /// Not used by the game.
+    pub author: BString,
+    /// Not used by the game.
+    pub steam_id: u64,
+    pub timestamp: u64,
+    /// Not used by the game.
+    pub addon_version: i32,
+    /// Only versions 2 and up can hold required content.
+    pub required_content: Vec<BString>,
+    /// Defaults to `VERSION`. Older versions are for tools that can't read
+    /// the current one.
+    pub format_version: u8,
+}
+
+impl AddonHeader {
+    /// A header with the defaults gmad has always written, stamped with the
+    /// current time.
+    pub fn new(title: &str, description: &str) -> AddonHeader {
+        AddonHeader {
+            title: title.into(),
+            description: description.into(),
+            author: "Author Name".into(),
+            steam_id: 0,
+            timestamp: bootil::time::unix_timestamp(),
+            addon_version: 1,
+            required_content: Vec::new(),
+            format_version: VERSION,
+        }
+    }
+}
+
+pub fn version_supported(version: u8) -> bool {
+    (MIN_VERSION..=VERSION).contains(&version)
+}
+
+/// Version 1 has no required content list, it was added in version 2.
+/// Otherwise the layout is the same in every version.
+pub fn has_required_content(version: u8) -> bool {
+    version > 1
+}
+
+pub mod tags {
+    pub const TYPE: [&str; 10] = [
+        "gamemode",
+        "map",
+        "weapon",
+        "vehicle",
+        "npc",
+        "entity",
+        "tool",
+        "effects",
+        "model",
+        "servercontent",
+    ];
+
+    pub fn type_exists(name: &str) -> bool {
+        TYPE.contains(&name)
+    }
+
+    pub const MISC: [&str; 9] = [
+        "fun", "roleplay", "scenic", "movie", "realism", "cartoon", "water", "comic", "build",
+    ];
+
+    pub fn tag_exists(name: &str) -> bool {
+        MISC.contains(&name)
+    }
+}
+
+pub const TIMESTAMP_OFFSET: u32 =
+    std::mem::size_of::<Header>() as u32 + std::mem::size_of::<u64>() as u32;
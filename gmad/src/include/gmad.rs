@@ -1,15 +0,0 @@
-use bootil::bstring::BString;
-
-pub fn create_addon_file(str_folder: BString, str_outfile: BString, warn_invalid: bool) -> i32 {
-    // Implementation code for CreateAddonFile goes here
-    // Replace Bootil::BString, Bootil::File, and other Bootil functions with their Rust equivalents
-    // Return the appropriate value based on the implementation
-    unimplemented!()
-}
-
-pub fn extract_addon_file(str_file: BString, str_out_path: BString) -> i32 {
-    // Implementation code for ExtractAddonFile goes here
-    // Replace Bootil::BString, Bootil::File, and other Bootil functions with their Rust equivalents
-    // Return the appropriate value based on the implementation
-    unimplemented!()
-}
\ No newline at end of file
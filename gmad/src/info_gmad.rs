@@ -0,0 +1,74 @@
+use bootil::{msg, string, time, BString};
+
+use crate::reader::Reader;
+use crate::report::Report;
+
+pub fn info_addon_file(str_file: BString, json: bool) -> i32 {
+    let mut report = Report::new(json, "info");
+    report.tree().set_child("file", &str_file);
+
+    let mut addon = match Reader::open(&str_file) {
+        Ok(addon) => addon,
+        Err(err) => return report.fail(&format!("There was a problem opening the file: {}", err)),
+    };
+
+    if let Err(err) = addon.parse() {
+        return report.fail(&format!("There was a problem parsing the file: {}", err));
+    }
+
+    let total: u64 = addon.iter().map(|e| e.i_size as u64).sum();
+
+    msg!("File:\t\t{}\n", str_file);
+    msg!("Title:\t\t{}\n", addon.title());
+    msg!("Type:\t\t{}\n", addon.addon_type());
+    msg!("Tags:\t\t{}\n", addon.tags().join(", "));
+    msg!("Author:\t\t{}\n", addon.author());
+    msg!("Format version:\t{}\n", addon.get_format_version());
+    msg!("Addon version:\t{}\n", addon.addon_version());
+    msg!("SteamID:\t{}\n", addon.steam_id());
+    msg!(
+        "Timestamp:\t{} ({} UTC)\n",
+        addon.timestamp(),
+        time::format_utc(addon.timestamp())
+    );
+    msg!("Files:\t\t{}\n", addon.len());
+    msg!(
+        "Size:\t\t{} ({} bytes)\n",
+        string::format::memory(total),
+        total
+    );
+
+    if !addon.required_content().is_empty() {
+        msg!("Requires:\t{}\n", addon.required_content().join(", "));
+    }
+
+    msg!("Description:\n");
+    for str_line in addon.description().lines() {
+        msg!("\t{}\n", str_line);
+    }
+
+    if report.is_enabled() {
+        let tree = report.tree();
+        tree.set_child("title", addon.title());
+        tree.set_child("type", addon.addon_type());
+        let tag_list = tree.add_array("tags");
+        for tag in addon.tags() {
+            tag_list.add_child("").set_value(tag);
+        }
+        tree.set_child("author", addon.author());
+        tree.set_child("description", addon.description());
+        tree.set_child_number("format_version", addon.get_format_version());
+        tree.set_child_number("addon_version", addon.addon_version());
+        // A SteamID64 doesn't fit in a double, keep it a string
+        tree.set_child("steamid", &addon.steam_id().to_string());
+        tree.set_child_number("timestamp", addon.timestamp());
+        tree.set_child_number("files", addon.len());
+        tree.set_child_number("size", total);
+        let required_list = tree.add_array("required_content");
+        for str_content in addon.required_content() {
+            required_list.add_child("").set_value(str_content);
+        }
+    }
+
+    report.finish(0)
+}
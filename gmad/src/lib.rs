@@ -0,0 +1,28 @@
+//! Reading and writing Garry's Mod addons (.gma), along with the commands
+//! the gmad tool is built from.
+
+pub mod addon_json;
+pub mod builder;
+pub mod compression;
+pub mod error;
+pub mod format;
+pub mod reader;
+pub mod text_diff;
+pub mod whitelist;
+pub mod writer;
+
+pub mod cat_gmad;
+pub mod create_gmad;
+pub mod diff_gmad;
+pub mod extract_gmad;
+pub mod info_gmad;
+pub mod list_gmad;
+pub mod verify_gmad;
+
+mod report;
+
+pub use builder::AddonBuilder;
+pub use error::GmadError;
+pub use format::{AddonHeader, FileEntry};
+pub use reader::Reader;
+pub use writer::Writer;
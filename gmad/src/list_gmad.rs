@@ -0,0 +1,135 @@
+use bootil::{msg, string, BString};
+
+use crate::format::FileEntry;
+use crate::reader::Reader;
+use crate::report::Report;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ListOrder {
+    /// The order of the index.
+    #[default]
+    Index,
+    Name,
+    /// Largest first.
+    Size,
+}
+
+impl ListOrder {
+    pub fn from_name(str_name: &str) -> Option<ListOrder> {
+        match string::get_lower(str_name).as_str() {
+            "index" | "" => Some(ListOrder::Index),
+            "name" => Some(ListOrder::Name),
+            "size" => Some(ListOrder::Size),
+            _ => None,
+        }
+    }
+
+    pub fn sort(self, entries: &mut [FileEntry]) {
+        match self {
+            ListOrder::Index => entries.sort_by_key(|e| e.i_file_number),
+            ListOrder::Name => entries.sort_by(|a, b| a.str_name.cmp(&b.str_name)),
+            ListOrder::Size => entries.sort_by(|a, b| {
+                b.i_size
+                    .cmp(&a.i_size)
+                    .then(a.i_file_number.cmp(&b.i_file_number))
+            }),
+        }
+    }
+}
+
+pub fn list_addon_file(str_file: BString, order: ListOrder, json: bool) -> i32 {
+    let mut report = Report::new(json, "list");
+    report.tree().set_child("file", &str_file);
+
+    let mut addon = match Reader::open(&str_file) {
+        Ok(addon) => addon,
+        Err(err) => return report.fail(&format!("There was a problem opening the file: {}", err)),
+    };
+
+    if let Err(err) = addon.parse() {
+        return report.fail(&format!("There was a problem parsing the file: {}", err));
+    }
+
+    let mut entries = addon.get_list().to_vec();
+    order.sort(&mut entries);
+
+    msg!(
+        "{:>6}  {:>10}  {:>8}  {:>12}  Name\n",
+        "#",
+        "Size",
+        "CRC",
+        "Offset"
+    );
+    for entry in &entries {
+        msg!(
+            "{:>6}  {:>10}  {:08x}  {:>12}  {}\n",
+            entry.i_file_number,
+            string::format::memory(entry.i_size as u64),
+            entry.i_crc,
+            entry.i_offset,
+            entry.str_name
+        );
+    }
+
+    let total: u64 = entries.iter().map(|e| e.i_size as u64).sum();
+    msg!(
+        "{} files, {} ({} bytes)\n",
+        entries.len(),
+        string::format::memory(total),
+        total
+    );
+
+    if report.is_enabled() {
+        let entry_list = report.tree().add_array("entries");
+        for entry in &entries {
+            let item = entry_list.add_object("");
+            item.set_child_number("number", entry.i_file_number);
+            item.set_child("name", &entry.str_name);
+            item.set_child_number("size", entry.i_size);
+            item.set_child("crc", &format!("{:08x}", entry.i_crc));
+            item.set_child_number("offset", entry.i_offset);
+        }
+        report.tree().set_child_number("count", entries.len());
+        report.tree().set_child_number("total_size", total);
+    }
+
+    report.finish(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(i_file_number: u32, str_name: &str, i_size: i64) -> FileEntry {
+        FileEntry {
+            str_name: str_name.into(),
+            i_size,
+            i_file_number,
+            ..FileEntry::default()
+        }
+    }
+
+    #[test]
+    fn test_list_order() {
+        let mut entries = vec![
+            entry(1, "materials/b.vmt", 10),
+            entry(2, "lua/a.lua", 300),
+            entry(3, "sound/c.wav", 10),
+        ];
+
+        ListOrder::Name.sort(&mut entries);
+        let names: Vec<_> = entries.iter().map(|e| e.i_file_number).collect();
+        assert_eq!(names, [2, 1, 3]);
+
+        ListOrder::Size.sort(&mut entries);
+        let sizes: Vec<_> = entries.iter().map(|e| e.i_file_number).collect();
+        assert_eq!(sizes, [2, 1, 3]);
+
+        ListOrder::Index.sort(&mut entries);
+        let index: Vec<_> = entries.iter().map(|e| e.i_file_number).collect();
+        assert_eq!(index, [1, 2, 3]);
+
+        assert_eq!(ListOrder::from_name("SIZE"), Some(ListOrder::Size));
+        assert_eq!(ListOrder::from_name("date"), None);
+    }
+}
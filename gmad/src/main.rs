@@ -1,66 +1,307 @@
-use bootil::{BString, Output, CommandLine, File, String};
-use std::fmt::Debug;
+use bootil::console::{self, Color};
+use bootil::{command_line, file, msg, output, string, BString};
+
+use gmad::cat_gmad::cat_addon_file;
+use gmad::create_gmad::{create_addon_file, CreateOptions};
+use gmad::diff_gmad::diff_addon_files;
+use gmad::extract_gmad::{extract_addon_file, ExtractOptions};
+use gmad::info_gmad::info_addon_file;
+use gmad::list_gmad::{list_addon_file, ListOrder};
+use gmad::verify_gmad::verify_addon_file;
+
 fn main() {
-    Debug::SuppressPopups(true);
-    CommandLine::Set(std::env::args().collect::<Vec<String>>().len(), std::env::args().collect::<Vec<String>>().as_slice());
-    Console::FGColorPush(Console::Green);
-    Output::Msg("Garry's Mod Addon Creator 1.1\n");
-    Console::FGColorPop();
+    command_line::set(std::env::args().skip(1));
 
-    let str_command = String::GetLower(CommandLine::GetArg(0));
+    let str_command = string::get_lower(&command_line::get_arg(0, ""));
 
-    if str_command == "create" || File::IsFolder(str_command.clone()) {
-        let mut str_folder = CommandLine::GetSwitch("-folder", "");
+    // With -json stdout carries nothing but the JSON document
+    let json = wants_json()
+        && ["create", "list", "info", "verify", "diff"].contains(&str_command.as_str());
+
+    // cat and create -out - write data to stdout, so everything else has to
+    // go elsewhere
+    let data_to_stdout = str_command == "cat" || command_line::get_switch("-out", "") == "-";
+    if data_to_stdout {
+        output::set_stderr(true);
+    }
+
+    if !json && !data_to_stdout {
+        console::fg_color_push(Color::Green);
+        msg!("Garry's Mod Addon Creator 1.1\n");
+        console::fg_color_pop();
+    }
+
+    if str_command == "create" || file::is_folder(&command_line::get_arg(0, "")) {
+        let mut str_folder = command_line::get_switch("-folder", "");
 
         if str_folder.is_empty() && str_command != "create" {
-            str_folder = str_command.clone();
+            str_folder = command_line::get_arg(0, "");
         }
 
         if str_folder.is_empty() {
-            Output::Msg("Missing -folder (the folder to turn into an addon)\n");
+            msg!("Missing -folder (the folder to turn into an addon)\n");
             std::process::exit(1);
         }
 
-        let mut str_target = CommandLine::GetSwitch("-out", "");
+        let str_target = command_line::get_switch("-out", "");
 
-        let warn_on_invalid_files = CommandLine::GetFull().find("-warninvalid").is_some();
+        if json && str_target == "-" {
+            msg!("-json can't be used with -out - (both write to stdout)\n");
+            std::process::exit(1);
+        }
 
-        std::process::exit(CreateAddonFile(str_folder, str_target, warn_on_invalid_files));
+        let defaults = CreateOptions::default();
+        let options = CreateOptions {
+            warn_invalid: command_line::has_switch("-warninvalid"),
+            steam_id: get_number_switch("-steamid").unwrap_or(defaults.steam_id),
+            timestamp: get_number_switch("-timestamp"),
+            addon_version: get_number_switch("-addonversion").unwrap_or(defaults.addon_version),
+            reproducible: command_line::has_switch("-reproducible"),
+            format_version: get_number_switch("-formatversion")
+                .or_else(|| get_number_switch("--format-version"))
+                .unwrap_or(defaults.format_version),
+            compress: command_line::has_switch("-compress"),
+            with_crc: !command_line::has_switch("-nocrc"),
+        };
+
+        std::process::exit(create_addon_file(str_folder, str_target, &options, json));
     }
 
-    if str_command == "extract" || String::File::GetFileExtension(str_command.clone()) == "gma" {
-        let mut str_file = CommandLine::GetSwitch("-file", "");
+    if str_command == "extract" || string::file::get_file_extension(&str_command) == "gma" {
+        let mut str_file = command_line::get_switch("-file", "");
+
+        if str_file.is_empty() {
+            str_file = if str_command == "extract" {
+                get_file_arg()
+            } else {
+                command_line::get_arg(0, "")
+            };
+        }
 
-        if str_file.is_empty() && str_command != "extract" {
-            str_file = str_command.clone();
+        if str_file.is_empty() {
+            msg!("Missing -file (the addon you want to extract)\n");
+            std::process::exit(1);
         }
 
+        let str_target = command_line::get_switch("-out", "");
+
+        let options = ExtractOptions {
+            unsafe_to_badnames: command_line::has_switch("-badnames"),
+            check_crc: command_line::has_switch("-checkcrc"),
+            skip_corrupt: command_line::has_switch("-skipcorrupt"),
+            include: command_line::get_switches("-include"),
+            exclude: command_line::get_switches("-exclude"),
+        };
+
+        std::process::exit(extract_addon_file(str_file, str_target, &options));
+    }
+
+    if str_command == "list" {
+        let str_file = get_file_arg();
+
         if str_file.is_empty() {
-            Output::Msg("Missing -file (the addon you want to extract)\n");
+            msg!("Missing -file (the addon you want to list)\n");
             std::process::exit(1);
         }
 
-        let mut str_target = CommandLine::GetSwitch("-out", "");
+        let str_order = command_line::get_switch("-sort", "");
+        let Some(order) = ListOrder::from_name(&str_order) else {
+            msg!("-sort must be one of index, name or size\n");
+            std::process::exit(1);
+        };
 
-        std::process::exit(ExtractAddonFile(str_file, str_target));
+        std::process::exit(list_addon_file(str_file, order, json));
     }
 
-    Output::Msg("\nUsage:\n\n");
-    Output::Msg("\tDrag'n'drop a .gma onto the gmad.exe to extract it\n");
-    Output::Msg("\tDrag'n'drop a folder onto the gmad.exe to convert it to a .gma\n\n");
-    Output::Msg("\tgmad.exe create -folder path/to/folder -out path/to/gma.gma\n");
-    Output::Msg("\tgmad.exe create -folder path/to/folder\n");
-    Output::Msg("\tgmad.exe extract -file path/to/gma.gma -out path/to/folder\n");
-    Output::Msg("\tgmad.exe extract -file path/to/gma.gma\n\n");
-    Output::Msg("\tAdd -warninvalid to automatically skip invalid files\n\n");
+    if str_command == "info" {
+        let str_file = get_file_arg();
+
+        if str_file.is_empty() {
+            msg!("Missing -file (the addon you want to inspect)\n");
+            std::process::exit(1);
+        }
+
+        std::process::exit(info_addon_file(str_file, json));
+    }
+
+    if str_command == "cat" {
+        // The addon and the entry can both be given as switches or as
+        // arguments after the command
+        let mut plain_args = get_plain_args().into_iter().skip(1);
+        let mut str_file = command_line::get_switch("-file", "");
+        if str_file.is_empty() {
+            str_file = plain_args.next().unwrap_or_default();
+        }
+        let mut str_name = command_line::get_switch("-name", "");
+        if str_name.is_empty() {
+            str_name = plain_args.next().unwrap_or_default();
+        }
+
+        if str_file.is_empty() || str_name.is_empty() {
+            msg!("Usage: cat path/to/gma.gma path/inside/addon.lua\n");
+            std::process::exit(1);
+        }
+
+        std::process::exit(cat_addon_file(str_file, str_name));
+    }
+
+    if str_command == "verify" {
+        let str_file = get_file_arg();
+
+        if str_file.is_empty() {
+            msg!("Missing -file (the addon you want to verify)\n");
+            std::process::exit(1);
+        }
+
+        std::process::exit(verify_addon_file(str_file, json));
+    }
+
+    if str_command == "diff" {
+        // Switches like -json and -text can go anywhere
+        let mut plain_args = get_plain_args().into_iter().skip(1);
+        let str_old = plain_args.next().unwrap_or_default();
+        let str_new = plain_args.next().unwrap_or_default();
+
+        if str_old.is_empty() || str_new.is_empty() {
+            msg!("Usage: diff path/to/old.gma path/to/new.gma [-text]\n");
+            std::process::exit(1);
+        }
+
+        std::process::exit(diff_addon_files(
+            str_old,
+            str_new,
+            command_line::has_switch("-text"),
+            json,
+        ));
+    }
+
+    msg!("\nUsage:\n\n");
+    msg!("\tDrag'n'drop a .gma onto the gmad.exe to extract it\n");
+    msg!("\tDrag'n'drop a folder onto the gmad.exe to convert it to a .gma\n\n");
+    msg!("\tgmad.exe create -folder path/to/folder -out path/to/gma.gma\n");
+    msg!("\tgmad.exe create -folder path/to/folder\n");
+    msg!("\tgmad.exe extract -file path/to/gma.gma -out path/to/folder\n");
+    msg!("\tgmad.exe extract -file path/to/gma.gma\n");
+    msg!("\tgmad.exe extract -file path/to/gma.gma -include \"lua/*\" -exclude \"*.txt\"\n");
+    msg!("\tgmad.exe list -file path/to/gma.gma [-sort index|name|size]\n");
+    msg!("\tgmad.exe verify -file path/to/gma.gma\n");
+    msg!("\tgmad.exe info -file path/to/gma.gma\n");
+    msg!("\tgmad.exe cat path/to/gma.gma lua/autorun/init.lua > init.lua\n");
+    msg!("\tgmad.exe cat -file path/to/gma.gma -name lua/autorun/init.lua > init.lua\n");
+    msg!("\tgmad.exe diff path/to/old.gma path/to/new.gma [-text]\n\n");
+    msg!("\tUse - as the file to read the addon from stdin, or -out - to write a created addon to stdout\n");
+    msg!("\tAdd -warninvalid to automatically skip invalid files\n");
+    msg!("\tAdd -nocrc to create an addon without CRCs\n");
+    msg!("\tAdd -steamid, -timestamp or -addonversion to set those header fields when creating\n");
+    msg!(
+        "\tAdd -formatversion 1 or 2 (or --format-version) to create an addon in an older format\n"
+    );
+    msg!("\tAdd -compress to create an LZMA compressed addon, like the game's cache (any command reads these)\n");
+    msg!("\tAdd -reproducible to create byte-identical addons from identical folders (honours SOURCE_DATE_EPOCH)\n");
+    msg!("\tAdd -badnames to extract files with unsafe names into badnames/ instead of skipping them\n");
+    msg!("\tAdd -include or -exclude (more than once if needed) to only extract files matching a pattern\n");
+    msg!("\tAdd -checkcrc to warn about corrupt files while extracting, or -skipcorrupt to leave them out\n");
+    msg!("\t(extract exits with 1 if a file failed the CRC check or couldn't be written)\n");
+    msg!("\tAdd -text to diff to show what changed inside .lua, .txt and .properties files\n");
+    msg!("\tAdd -json (or -format json) to create, list, info, verify or diff to print the result as JSON\n\n");
 
     #[cfg(target_os = "windows")]
     {
         // Make sure they see how to use it
         // Linux folks are too smart for this
-        std::process::Command::new("cmd")
-            .args(&["/C", "pause"])
-            .status()
-            .unwrap();
+        let _ = std::process::Command::new("cmd")
+            .args(["/C", "pause"])
+            .status();
+    }
+}
+
+/// The addon a command works on: `-file`, or the first plain argument after
+/// the command, wherever the switches are. `-` means stdin.
+fn get_file_arg() -> BString {
+    let str_file = command_line::get_switch("-file", "");
+    if !str_file.is_empty() {
+        return str_file;
+    }
+
+    get_plain_args().into_iter().nth(1).unwrap_or_default()
+}
+
+/// Switches that take the argument after them as their value.
+const SWITCHES_WITH_VALUES: &[&str] = &[
+    "-file",
+    "-folder",
+    "-out",
+    "-name",
+    "-sort",
+    "-include",
+    "-exclude",
+    "-steamid",
+    "-timestamp",
+    "-addonversion",
+    "-formatversion",
+    "--format-version",
+    "-format",
+    "--format",
+];
+
+/// The arguments that aren't switches or their values, starting with the
+/// command.
+fn get_plain_args() -> Vec<BString> {
+    command_line::get_plain_args(SWITCHES_WITH_VALUES)
+}
+
+/// `-json`, `--json` or `-format json`.
+fn wants_json() -> bool {
+    command_line::has_switch("-json")
+        || command_line::has_switch("--json")
+        || string::get_lower(&command_line::get_switch("-format", "")) == "json"
+        || string::get_lower(&command_line::get_switch("--format", "")) == "json"
+}
+
+/// Parses the value of a numeric switch. Exits if it isn't a number.
+fn get_number_switch<T: std::str::FromStr>(name: &str) -> Option<T> {
+    let str_value = command_line::get_switch(name, "");
+    if str_value.is_empty() {
+        return None;
+    }
+
+    match str_value.parse() {
+        Ok(value) => Some(value),
+        Err(_) => {
+            msg!("{} must be a number, not \"{}\"\n", name, str_value);
+            std::process::exit(1);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_get_file_arg() {
+        let file_arg = |args: &[&str]| {
+            command_line::set(args.iter().copied());
+            get_file_arg()
+        };
+
+        assert_eq!(file_arg(&["list", "a.gma", "--json"]), "a.gma");
+        assert_eq!(file_arg(&["list", "--json", "a.gma"]), "a.gma");
+        assert_eq!(file_arg(&["info", "-format", "json", "a.gma"]), "a.gma");
+        assert_eq!(file_arg(&["verify", "-json", "a.gma"]), "a.gma");
+        assert_eq!(file_arg(&["verify", "-json", "-"]), "-");
+        assert_eq!(
+            file_arg(&["info", "-json", "-file", "b.gma", "a.gma"]),
+            "b.gma"
+        );
+        assert_eq!(file_arg(&["info", "-json"]), "");
+
+        // Switches with values before the file
+        assert_eq!(file_arg(&["list", "-sort", "size", "a.gma"]), "a.gma");
+        assert_eq!(file_arg(&["extract", "-out", "dir", "a.gma"]), "a.gma");
+        assert_eq!(
+            file_arg(&["extract", "-include", "lua/*", "-checkcrc", "a.gma"]),
+            "a.gma"
+        );
     }
-}
\ No newline at end of file
+}
@@ -0,0 +1,1013 @@
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, Cursor, Read, Seek, SeekFrom, Write};
+
+use bootil::buffer::StringTooLong;
+use bootil::data::json;
+use bootil::hasher::crc32::Crc32;
+use bootil::string::get_lower;
+use bootil::{warning, BString, BinaryRead, Buffer, Tree};
+
+use crate::compression;
+use crate::error::GmadError;
+use crate::format::{self, FileEntry, FileEntryList, IDENT};
+
+/// A stored CRC next to the one computed from the data.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CrcCheck {
+    pub expected: u32,
+    pub actual: u32,
+}
+
+impl CrcCheck {
+    pub fn is_ok(&self) -> bool {
+        self.expected == self.actual
+    }
+}
+
+/// An entry whose contents don't match the CRC in the index.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CorruptEntry {
+    pub file_number: u32,
+    pub name: BString,
+    pub expected: u32,
+    pub actual: u32,
+}
+
+impl From<CorruptEntry> for GmadError {
+    fn from(entry: CorruptEntry) -> GmadError {
+        GmadError::CrcMismatch {
+            name: entry.name,
+            expected: entry.expected,
+            actual: entry.actual,
+        }
+    }
+}
+
+/// What `Reader::verify` found.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct VerifyReport {
+    pub corrupt: Vec<CorruptEntry>,
+    /// Entries stored without a CRC, which couldn't be checked.
+    pub unchecked: Vec<BString>,
+    /// The whole-addon CRC, or None if the addon doesn't have one.
+    pub addon_crc: Option<CrcCheck>,
+}
+
+impl VerifyReport {
+    pub fn is_ok(&self) -> bool {
+        self.corrupt.is_empty() && self.addon_crc.is_none_or(|crc| crc.is_ok())
+    }
+}
+
+/// Reads an addon from any seekable stream.
+///
+/// Only the header and file index are read by `parse`; file contents stay in
+/// the stream until they're asked for, so opening a huge addon is cheap.
+/// Entries are read with many small reads, so the stream should be buffered.
+/// `Reader::open` is the way to read an addon file.
+pub struct Reader<R = Input> {
+    stream: R,
+    path: BString,
+    fmt_version: u8,
+    steam_id: u64,
+    timestamp: u64,
+    required_content: Vec<BString>,
+    name: BString,
+    author: BString,
+    desc: BString,
+    addon_version: i32,
+    addon_type: BString,
+    file_block: u64,
+    tags: Vec<BString>,
+    index: FileEntryList,
+    /// Position in `index` of each entry, by name and by file number.
+    by_name: HashMap<BString, usize>,
+    by_number: HashMap<u32, usize>,
+}
+
+/// Where an addon is read from: a file, or stdin. Reading needs to seek, so
+/// stdin is read into memory first.
+pub enum Input {
+    File(BufReader<File>),
+    Memory(Cursor<Vec<u8>>),
+}
+
+impl Read for Input {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        match self {
+            Input::File(file) => file.read(buf),
+            Input::Memory(memory) => memory.read(buf),
+        }
+    }
+}
+
+impl Seek for Input {
+    fn seek(&mut self, pos: SeekFrom) -> io::Result<u64> {
+        match self {
+            Input::File(file) => file.seek(pos),
+            Input::Memory(memory) => memory.seek(pos),
+        }
+    }
+}
+
+impl Reader<Input> {
+    /// Opens the named file, or reads stdin when the name is `-`. Compressed
+    /// addons are decompressed into memory.
+    pub fn open(file_name: &str) -> Result<Self, GmadError> {
+        if file_name != "-" {
+            let file = File::open(file_name).map_err(|e| GmadError::io(file_name, e))?;
+            let mut file = BufReader::new(file);
+            let start = file.fill_buf().map_err(|e| GmadError::io(file_name, e))?;
+            if !compression::is_compressed(start) {
+                return Ok(Reader::new(Input::File(file)).with_path(file_name));
+            }
+
+            let mut data = Vec::new();
+            file.read_to_end(&mut data)
+                .map_err(|e| GmadError::io(file_name, e))?;
+            let data = compression::decompress(&data, compression::MAX_SIZE)?;
+            return Ok(Reader::new(Input::Memory(Cursor::new(data))).with_path(file_name));
+        }
+
+        let mut data = Vec::new();
+        io::stdin()
+            .lock()
+            .read_to_end(&mut data)
+            .map_err(|e| GmadError::io("<stdin>", e))?;
+        if compression::is_compressed(&data) {
+            data = compression::decompress(&data, compression::MAX_SIZE)?;
+        }
+        Ok(Reader::new(Input::Memory(Cursor::new(data))).with_path("<stdin>"))
+    }
+}
+
+impl<R: Read + Seek> Reader<R> {
+    pub fn new(stream: R) -> Reader<R> {
+        Reader {
+            stream,
+            path: BString::new(),
+            fmt_version: 0,
+            steam_id: 0,
+            timestamp: 0,
+            required_content: Vec::new(),
+            name: BString::new(),
+            author: BString::new(),
+            desc: BString::new(),
+            addon_version: 0,
+            addon_type: BString::new(),
+            file_block: 0,
+            tags: Vec::new(),
+            index: FileEntryList::new(),
+            by_name: HashMap::new(),
+            by_number: HashMap::new(),
+        }
+    }
+
+    /// Names the file the stream came from, for error messages.
+    pub fn with_path(mut self, path: &str) -> Self {
+        self.path = path.into();
+        self
+    }
+
+    pub fn parse(&mut self) -> Result<(), GmadError> {
+        self.clear();
+
+        let path = self.path.clone();
+        let stream = &mut self.stream;
+        let header_error = |e: io::Error| eof_or_io(e, &path, GmadError::TruncatedHeader);
+        let index_error = |e: io::Error| eof_or_io(e, &path, GmadError::TruncatedIndex);
+
+        // Everything in the header gets checked against the real length
+        let file_len = stream
+            .seek(SeekFrom::End(0))
+            .map_err(|e| GmadError::io(&path, e))?;
+        stream.seek(SeekFrom::Start(0)).map_err(header_error)?;
+
+        // Ident
+        let mut ident = [0u8; 4];
+        stream.read_exact(&mut ident).map_err(header_error)?;
+        if &ident != IDENT {
+            return Err(GmadError::BadIdent);
+        }
+
+        // Format Version
+        self.fmt_version = stream.read_u8().map_err(header_error)?;
+
+        if !format::version_supported(self.fmt_version) {
+            return Err(GmadError::UnsupportedVersion(self.fmt_version));
+        }
+
+        self.steam_id = stream.read_u64().map_err(header_error)?;
+        self.timestamp = stream.read_u64().map_err(header_error)?;
+
+        // Required content, a list of strings ending with an empty one
+        if format::has_required_content(self.fmt_version) {
+            let mut str_content = read_string(stream, "Required content").map_err(header_error)?;
+
+            while !str_content.is_empty() {
+                self.required_content.push(str_content);
+                str_content = read_string(stream, "Required content").map_err(header_error)?;
+            }
+        }
+
+        self.name = read_string(stream, "Title").map_err(header_error)?;
+        self.desc = read_string(stream, "Description").map_err(header_error)?;
+        self.author = read_string(stream, "Author").map_err(header_error)?;
+
+        self.addon_version = stream.read_i32().map_err(header_error)?;
+
+        let mut i_file_number: u32 = 1;
+        let mut i_offset: i64 = 0;
+
+        while stream.read_u32().map_err(index_error)? != 0 {
+            let entry = FileEntry {
+                str_name: read_string(stream, "File name").map_err(index_error)?,
+                i_size: stream.read_i64().map_err(index_error)?,
+                i_crc: stream.read_u32().map_err(index_error)?,
+                i_file_number,
+                i_offset,
+            };
+
+            if entry.i_size < 0 {
+                return Err(GmadError::InvalidEntrySize {
+                    name: entry.str_name,
+                    size: entry.i_size,
+                });
+            }
+
+            i_offset =
+                i_offset
+                    .checked_add(entry.i_size)
+                    .ok_or_else(|| GmadError::EntryPastEnd {
+                        name: entry.str_name.clone(),
+                    })?;
+            i_file_number = i_file_number
+                .checked_add(1)
+                .ok_or(GmadError::TooManyFiles)?;
+
+            // If a name turns up twice, the first one wins
+            self.by_name
+                .entry(entry.str_name.clone())
+                .or_insert(self.index.len());
+            self.by_number.insert(entry.i_file_number, self.index.len());
+            self.index.push(entry);
+        }
+
+        self.file_block = stream.stream_position().map_err(index_error)?;
+
+        // Offsets and sizes are non-negative and can't overflow at this point
+        let content_len = file_len.saturating_sub(self.file_block);
+        if let Some(entry) = self
+            .index
+            .iter()
+            .find(|entry| entry.i_offset as u64 + entry.i_size as u64 > content_len)
+        {
+            return Err(GmadError::EntryPastEnd {
+                name: entry.str_name.clone(),
+            });
+        }
+
+        // Try to parse the description
+        let mut json = Tree::new();
+
+        if json::import(&mut json, &self.desc) {
+            self.desc = json.child_value("description", "");
+            self.addon_type = json.child_value("type", "");
+            self.tags = json
+                .get_child("tags")
+                .map(|tags| {
+                    tags.children()
+                        .iter()
+                        .map(|tag| tag.value().to_string())
+                        .collect()
+                })
+                .unwrap_or_default();
+        }
+
+        Ok(())
+    }
+
+    pub fn get_file(&self, file_id: u32) -> Option<FileEntry> {
+        self.entry(file_id).cloned()
+    }
+
+    /// The entry with this file number.
+    pub fn entry(&self, file_id: u32) -> Option<&FileEntry> {
+        self.by_number.get(&file_id).map(|&i| &self.index[i])
+    }
+
+    /// Looks an entry up by name. Names are stored in lower case with forward
+    /// slashes, so a name that doesn't match exactly is tried that way too.
+    pub fn find(&self, str_name: &str) -> Option<&FileEntry> {
+        let i = match self.by_name.get(str_name) {
+            Some(&i) => i,
+            None => *self.by_name.get(&get_lower(&str_name.replace('\\', "/")))?,
+        };
+        Some(&self.index[i])
+    }
+
+    /// The entries in index order.
+    pub fn iter(&self) -> std::slice::Iter<'_, FileEntry> {
+        self.index.iter()
+    }
+
+    pub fn len(&self) -> usize {
+        self.index.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.index.is_empty()
+    }
+
+    /// Appends the contents of the file to `buffer`.
+    pub fn read_file(&mut self, file_id: u32, buffer: &mut Buffer) -> Result<(), GmadError> {
+        self.write_file(file_id, buffer).map(|_| ())
+    }
+
+    /// Streams the contents of the file into `out` without holding the whole
+    /// entry in memory. Returns the number of bytes written. Errors writing
+    /// to `out` are reported without a path.
+    pub fn write_file<W: Write + ?Sized>(
+        &mut self,
+        file_id: u32,
+        out: &mut W,
+    ) -> Result<u64, GmadError> {
+        let file = self
+            .get_file(file_id)
+            .ok_or(GmadError::NoSuchFile(file_id))?;
+
+        self.copy_range(
+            self.file_block + file.i_offset as u64,
+            file.i_size as u64,
+            &file.str_name,
+            |data| out.write_all(data).map_err(|e| GmadError::io("", e)),
+        )?;
+
+        Ok(file.i_size as u64)
+    }
+
+    /// Like `write_file`, but also checks the contents against the CRC in the
+    /// index. The data has already been written by the time a mismatch is
+    /// reported. Entries stored without a CRC aren't checked.
+    pub fn write_file_checked<W: Write + ?Sized>(
+        &mut self,
+        file_id: u32,
+        out: &mut W,
+    ) -> Result<u64, GmadError> {
+        let file = self
+            .get_file(file_id)
+            .ok_or(GmadError::NoSuchFile(file_id))?;
+
+        let mut crc = Crc32::new();
+        self.copy_range(
+            self.file_block + file.i_offset as u64,
+            file.i_size as u64,
+            &file.str_name,
+            |data| {
+                crc.update(data);
+                out.write_all(data).map_err(|e| GmadError::io("", e))
+            },
+        )?;
+
+        if file.i_crc != 0 && crc.finalize() != file.i_crc {
+            return Err(GmadError::CrcMismatch {
+                name: file.str_name,
+                expected: file.i_crc,
+                actual: crc.finalize(),
+            });
+        }
+
+        Ok(file.i_size as u64)
+    }
+
+    /// Recomputes the CRC of every entry, and of the whole addon, and compares
+    /// them with the stored ones.
+    pub fn verify(&mut self) -> Result<VerifyReport, GmadError> {
+        let mut report = VerifyReport::default();
+        let mut addon_crc = Crc32::new();
+
+        // Header and index
+        self.copy_range(0, self.file_block, "", |data| {
+            addon_crc.update(data);
+            Ok(())
+        })?;
+
+        let entries = self.index.clone();
+        let mut contents_end = self.file_block;
+        for entry in &entries {
+            let start = self.file_block + entry.i_offset as u64;
+            let mut crc = Crc32::new();
+            self.copy_range(start, entry.i_size as u64, &entry.str_name, |data| {
+                crc.update(data);
+                addon_crc.update(data);
+                Ok(())
+            })?;
+            contents_end = start + entry.i_size as u64;
+
+            if entry.i_crc == 0 {
+                report.unchecked.push(entry.str_name.clone());
+            } else if crc.finalize() != entry.i_crc {
+                report.corrupt.push(CorruptEntry {
+                    file_number: entry.i_file_number,
+                    name: entry.str_name.clone(),
+                    expected: entry.i_crc,
+                    actual: crc.finalize(),
+                });
+            }
+        }
+
+        // The addon CRC follows the contents. Older or -nocrc addons leave it
+        // out or set it to 0.
+        report.addon_crc = self
+            .stream
+            .seek(SeekFrom::Start(contents_end))
+            .and_then(|_| self.stream.read_u32())
+            .ok()
+            .filter(|&expected| expected != 0)
+            .map(|expected| CrcCheck {
+                expected,
+                actual: addon_crc.finalize(),
+            });
+
+        Ok(report)
+    }
+
+    /// Feeds `len` bytes starting at `start` to `sink`, a chunk at a time.
+    fn copy_range(
+        &mut self,
+        start: u64,
+        len: u64,
+        str_name: &str,
+        mut sink: impl FnMut(&[u8]) -> Result<(), GmadError>,
+    ) -> Result<(), GmadError> {
+        self.stream
+            .seek(SeekFrom::Start(start))
+            .map_err(|e| GmadError::io(&self.path, e))?;
+
+        let mut remaining = len;
+        let mut chunk = vec![0u8; remaining.min(64 * 1024) as usize];
+        while remaining > 0 {
+            let want = remaining.min(chunk.len() as u64) as usize;
+            let count = match self.stream.read(&mut chunk[..want]) {
+                Ok(0) => {
+                    return Err(GmadError::EntryPastEnd {
+                        name: str_name.into(),
+                    })
+                }
+                Ok(count) => count,
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+                Err(e) => return Err(GmadError::io(&self.path, e)),
+            };
+            sink(&chunk[..count])?;
+            remaining -= count as u64;
+        }
+
+        Ok(())
+    }
+
+    pub fn clear(&mut self) {
+        self.fmt_version = 0;
+        self.steam_id = 0;
+        self.timestamp = 0;
+        self.addon_version = 0;
+        self.addon_type.clear();
+        self.required_content.clear();
+        self.name.clear();
+        self.author.clear();
+        self.desc.clear();
+        self.index.clear();
+        self.by_name.clear();
+        self.by_number.clear();
+        self.file_block = 0;
+        self.tags.clear();
+    }
+
+    pub fn get_list(&self) -> &[FileEntry] {
+        &self.index
+    }
+
+    pub fn get_format_version(&self) -> u8 {
+        self.fmt_version
+    }
+
+    /// SteamID of whoever built the addon. gmad writes 0.
+    pub fn steam_id(&self) -> u64 {
+        self.steam_id
+    }
+
+    /// When the addon was built, in seconds since the unix epoch.
+    pub fn timestamp(&self) -> u64 {
+        self.timestamp
+    }
+
+    /// The creator-assigned version. gmad writes 1.
+    pub fn addon_version(&self) -> i32 {
+        self.addon_version
+    }
+
+    /// Content the addon says it needs, as written by the creator.
+    pub fn required_content(&self) -> &[BString] {
+        &self.required_content
+    }
+
+    pub fn get_stream(&mut self) -> &mut R {
+        &mut self.stream
+    }
+
+    pub fn into_inner(self) -> R {
+        self.stream
+    }
+
+    pub fn title(&self) -> &str {
+        &self.name
+    }
+
+    pub fn description(&self) -> &str {
+        &self.desc
+    }
+
+    pub fn author(&self) -> &str {
+        &self.author
+    }
+
+    /// The addon type from the JSON description, or empty if there isn't one.
+    pub fn addon_type(&self) -> &str {
+        &self.addon_type
+    }
+
+    pub fn tags(&self) -> &[BString] {
+        &self.tags
+    }
+}
+
+/// A reader over a memory-mapped addon.
+#[cfg(feature = "mmap")]
+pub type MappedReader = Reader<Cursor<memmap2::Mmap>>;
+
+#[cfg(feature = "mmap")]
+impl MappedReader {
+    /// Maps the whole file into memory rather than reading it.
+    ///
+    /// The mapping assumes nothing else modifies or truncates the file while
+    /// the reader is alive. Compressed addons can't be mapped; `Reader::open`
+    /// reads those.
+    pub fn map_file(file_name: &str) -> Result<Self, GmadError> {
+        let file = File::open(file_name).map_err(|e| GmadError::io(file_name, e))?;
+        // SAFETY: the map is read-only, and the caller guarantees the file
+        // isn't changed underneath us.
+        let map = unsafe { memmap2::Mmap::map(&file) }.map_err(|e| GmadError::io(file_name, e))?;
+        if compression::is_compressed(&map) {
+            return Err(GmadError::BadCompression(
+                "compressed addons can't be mapped".into(),
+            ));
+        }
+        Ok(Reader::new(Cursor::new(map)).with_path(file_name))
+    }
+}
+
+/// Addons that are already in memory (including mapped ones) can hand out
+/// their contents without copying.
+impl<T: AsRef<[u8]>> Reader<Cursor<T>> {
+    /// The whole addon.
+    pub fn get_buffer(&self) -> &[u8] {
+        self.stream.get_ref().as_ref()
+    }
+
+    /// The contents of the file, borrowed from the addon.
+    pub fn read_file_slice(&self, file_id: u32) -> Option<&[u8]> {
+        let file = self.get_file(file_id)?;
+        let start = usize::try_from(self.file_block + file.i_offset as u64).ok()?;
+        self.get_buffer()
+            .get(start..start.checked_add(file.i_size as usize)?)
+    }
+}
+
+/// The longest string the reader will accept, as protection against archives
+/// that never terminate a string.
+pub const MAX_STRING_LENGTH: usize = 1024 * 1024;
+
+/// Reads a string from the header or index. The C++ gmad stores names as
+/// whatever bytes Windows gave it, usually cp1252, so strings that aren't
+/// UTF-8 are read as Latin-1 (the same for cp1252's accented letters) and
+/// warned about, since they no longer match the bytes in the file.
+fn read_string<R: Read>(stream: &mut R, what: &str) -> io::Result<BString> {
+    let bytes = stream.read_cstring_bytes(MAX_STRING_LENGTH)?;
+    match String::from_utf8(bytes) {
+        Ok(value) => Ok(value),
+        Err(err) => {
+            let value: BString = err.into_bytes().into_iter().map(char::from).collect();
+            let shown: BString = value.chars().take(80).collect();
+            warning!(
+                "{} \"{}\" isn't valid UTF-8, reading it as Latin-1\n",
+                what,
+                shown
+            );
+            Ok(value)
+        }
+    }
+}
+
+fn eof_or_io(err: io::Error, path: &str, truncated: GmadError) -> GmadError {
+    if err.kind() == io::ErrorKind::UnexpectedEof {
+        truncated
+    } else if err
+        .get_ref()
+        .is_some_and(|inner| inner.is::<StringTooLong>())
+    {
+        GmadError::StringTooLong
+    } else {
+        GmadError::io(path, err)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    use super::*;
+    use crate::create_gmad::create_addon;
+    use crate::format::{AddonHeader, VERSION};
+    use bootil::hasher::crc32;
+    use bootil::{AutoBuffer, BinaryWrite};
+
+    fn example_addon() -> AutoBuffer {
+        static COUNTER: AtomicUsize = AtomicUsize::new(0);
+        let folder = std::env::temp_dir().join(format!(
+            "gmad-reader-{}-{}",
+            std::process::id(),
+            COUNTER.fetch_add(1, Ordering::Relaxed)
+        ));
+        let folder = folder.to_string_lossy().replace('\\', "/") + "/";
+        bootil::file::create_folder(&format!("{}lua/autorun", folder), true);
+        bootil::file::write(&format!("{}lua/autorun/init.lua", folder), b"print()");
+
+        let mut buffer = AutoBuffer::new();
+        let description = "{\n\t\"description\":\t\"This is an example addon for testing.\",\n\t\"type\":\t\"tool\",\n\t\"tags\":\t[\"fun\"]\n}";
+        let header = AddonHeader {
+            steam_id: 76561197960287930,
+            timestamp: 1_700_000_000,
+            addon_version: 7,
+            required_content: vec!["workshop/104691717".to_string(), "base_content".to_string()],
+            ..AddonHeader::new("Example Addon", description)
+        };
+        create_addon::create(
+            &mut buffer,
+            &folder,
+            &["lua/autorun/init.lua".to_string()],
+            &header,
+            true,
+        )
+        .unwrap();
+        let _ = std::fs::remove_dir_all(&folder);
+        buffer
+    }
+
+    #[test]
+    fn test_reader() {
+        let path = std::env::temp_dir().join(format!("gmad-reader-{}.gma", std::process::id()));
+        let path = path.to_string_lossy().into_owned();
+        assert!(bootil::file::write(&path, example_addon().get_base()));
+
+        let mut reader = Reader::open(&path).unwrap();
+        reader.parse().unwrap();
+        let _ = std::fs::remove_file(&path);
+        assert!(matches!(reader.get_stream(), Input::File(_)));
+        assert_eq!(reader.title(), "Example Addon");
+        assert_eq!(
+            reader.description(),
+            "This is an example addon for testing."
+        );
+        assert_eq!(reader.author(), "Author Name");
+        assert_eq!(reader.tags(), ["fun"]);
+        assert_eq!(reader.addon_type(), "tool");
+        assert_eq!(
+            reader.required_content(),
+            ["workshop/104691717", "base_content"]
+        );
+        assert_eq!(reader.steam_id(), 76561197960287930);
+        assert_eq!(reader.timestamp(), 1_700_000_000);
+        assert_eq!(reader.addon_version(), 7);
+        assert_eq!(reader.get_format_version(), VERSION);
+        assert_eq!(reader.get_list().len(), 1);
+
+        let mut buffer = Buffer::new();
+        reader.read_file(1, &mut buffer).unwrap();
+        assert_eq!(buffer.get_base(), b"print()");
+    }
+
+    #[test]
+    fn test_reader_opens_compressed() {
+        let path =
+            std::env::temp_dir().join(format!("gmad-reader-{}-lzma.gma", std::process::id()));
+        let path = path.to_string_lossy().into_owned();
+        let addon = example_addon();
+        assert!(bootil::file::write(
+            &path,
+            &compression::compress(addon.get_base()).unwrap()
+        ));
+
+        let mut opened = Reader::open(&path).unwrap();
+        let result = opened.parse();
+        let _ = std::fs::remove_file(&path);
+        result.unwrap();
+        assert_eq!(opened.title(), "Example Addon");
+        assert!(matches!(opened.get_stream(), Input::Memory(_)));
+
+        let mut buffer = Buffer::new();
+        opened.read_file(1, &mut buffer).unwrap();
+        assert_eq!(buffer.get_base(), b"print()");
+    }
+
+    #[test]
+    fn test_reader_streams_entries() {
+        let mut reader = Reader::new(Cursor::new(example_addon().into_vec()));
+        reader.parse().unwrap();
+
+        assert_eq!(
+            reader.find("lua/autorun/init.lua").unwrap().i_file_number,
+            1
+        );
+        assert_eq!(
+            reader.find("LUA\\autorun\\Init.lua").unwrap().i_file_number,
+            1
+        );
+        assert!(reader.find("lua/autorun").is_none());
+
+        let mut out = Vec::new();
+        assert_eq!(reader.write_file(1, &mut out).unwrap(), 7);
+        assert_eq!(out, b"print()");
+        assert!(matches!(
+            reader.write_file(2, &mut out),
+            Err(GmadError::NoSuchFile(2))
+        ));
+    }
+
+    #[test]
+    fn test_reader_borrows_in_memory_entries() {
+        let mut reader = Reader::new(Cursor::new(example_addon().into_vec()));
+        reader.parse().unwrap();
+        assert_eq!(reader.read_file_slice(1), Some(&b"print()"[..]));
+        assert_eq!(reader.read_file_slice(2), None);
+        assert_eq!(&reader.get_buffer()[..4], IDENT);
+    }
+
+    #[cfg(feature = "mmap")]
+    #[test]
+    fn test_mapped_reader() {
+        let path = std::env::temp_dir().join(format!("gmad-reader-map-{}.gma", std::process::id()));
+        let path = path.to_string_lossy().into_owned();
+        assert!(bootil::file::write(&path, example_addon().get_base()));
+
+        let mut reader = MappedReader::map_file(&path).unwrap();
+        reader.parse().unwrap();
+        assert_eq!(reader.read_file_slice(1), Some(&b"print()"[..]));
+        drop(reader);
+        let _ = std::fs::remove_file(&path);
+    }
+
+    /// A version 3 addon with the given index and no file contents.
+    fn raw_addon(entries: &[(&[u8], i64)]) -> Vec<u8> {
+        let mut buffer = AutoBuffer::new();
+        buffer.write(IDENT);
+        buffer.write_u8(VERSION).unwrap();
+        buffer.write_u64(0).unwrap();
+        buffer.write_u64(0).unwrap();
+        buffer.write_cstring("").unwrap();
+        buffer.write_cstring("Title").unwrap();
+        buffer.write_cstring("Description").unwrap();
+        buffer.write_cstring("Author").unwrap();
+        buffer.write_i32(1).unwrap();
+        for (i, (name, size)) in entries.iter().enumerate() {
+            buffer.write_u32(i as u32 + 1).unwrap();
+            buffer.write(name);
+            buffer.write(&[0]);
+            buffer.write_i64(*size).unwrap();
+            buffer.write_u32(0).unwrap();
+        }
+        buffer.write_u32(0).unwrap();
+        buffer.into_vec()
+    }
+
+    /// An addon laid out by hand the way each format version has it.
+    fn versioned_addon(format_version: u8) -> Vec<u8> {
+        let mut data = Vec::new();
+        data.extend_from_slice(b"GMAD");
+        data.push(format_version);
+        data.extend_from_slice(&76561197960287930u64.to_le_bytes());
+        data.extend_from_slice(&1_100_000_000u64.to_le_bytes());
+        if format_version > 1 {
+            data.extend_from_slice(b"base_content\0\0");
+        }
+        data.extend_from_slice(b"Legacy\0Old description\0Someone\0");
+        data.extend_from_slice(&2i32.to_le_bytes());
+        data.extend_from_slice(&1u32.to_le_bytes());
+        data.extend_from_slice(b"lua/old.lua\0");
+        data.extend_from_slice(&5i64.to_le_bytes());
+        data.extend_from_slice(&crc32::easy(b"old()").to_le_bytes());
+        data.extend_from_slice(&0u32.to_le_bytes());
+        data.extend_from_slice(b"old()");
+        let addon_crc = crc32::easy(&data);
+        data.extend_from_slice(&addon_crc.to_le_bytes());
+        data
+    }
+
+    #[test]
+    fn test_reader_format_versions() {
+        for format_version in 1..=3 {
+            let mut reader = Reader::new(Cursor::new(versioned_addon(format_version)));
+            reader.parse().unwrap();
+            assert_eq!(reader.get_format_version(), format_version);
+            assert_eq!(reader.steam_id(), 76561197960287930);
+            assert_eq!(reader.timestamp(), 1_100_000_000);
+            assert_eq!(reader.title(), "Legacy");
+            // Not JSON, so it's kept as it is
+            assert_eq!(reader.description(), "Old description");
+            assert_eq!(reader.author(), "Someone");
+            assert_eq!(reader.addon_version(), 2);
+            if format_version > 1 {
+                assert_eq!(reader.required_content(), ["base_content"]);
+            } else {
+                assert!(reader.required_content().is_empty());
+            }
+            assert_eq!(reader.read_file_slice(1), Some(&b"old()"[..]));
+            assert!(reader.verify().unwrap().is_ok());
+        }
+    }
+
+    #[test]
+    fn test_reader_index_lookup() {
+        let names: Vec<String> = (0..20_000).map(|i| format!("lua/file{}.lua", i)).collect();
+        let entries: Vec<(&[u8], i64)> = names.iter().map(|name| (name.as_bytes(), 0)).collect();
+        let mut reader = Reader::new(Cursor::new(raw_addon(&entries)));
+        reader.parse().unwrap();
+
+        assert_eq!(reader.len(), names.len());
+        for (i, name) in names.iter().enumerate() {
+            let file_number = i as u32 + 1;
+            assert_eq!(reader.find(name).unwrap().i_file_number, file_number);
+            assert_eq!(&reader.entry(file_number).unwrap().str_name, name);
+        }
+        assert!(reader.entry(0).is_none());
+        assert!(reader.find("lua/missing.lua").is_none());
+        assert!(reader
+            .iter()
+            .map(|e| e.i_file_number)
+            .eq(1..=names.len() as u32));
+
+        // Duplicate names resolve to the first entry
+        let mut reader = Reader::new(Cursor::new(raw_addon(&[
+            (b"lua/a.lua", 0),
+            (b"lua/a.lua", 0),
+        ])));
+        reader.parse().unwrap();
+        assert_eq!(reader.find("lua/a.lua").unwrap().i_file_number, 1);
+        assert_eq!(reader.len(), 2);
+    }
+
+    #[test]
+    fn test_reader_rejects_malicious_index() {
+        let mut reader = Reader::new(Cursor::new(raw_addon(&[(b"lua/a.lua", -5)])));
+        assert!(matches!(
+            reader.parse(),
+            Err(GmadError::InvalidEntrySize { size: -5, .. })
+        ));
+
+        let mut reader = Reader::new(Cursor::new(raw_addon(&[(b"lua/a.lua", 1 << 40)])));
+        assert!(matches!(
+            reader.parse(),
+            Err(GmadError::EntryPastEnd { .. })
+        ));
+
+        // Sizes that only overflow once they're added up
+        let mut reader = Reader::new(Cursor::new(raw_addon(&[
+            (b"lua/a.lua", i64::MAX),
+            (b"lua/b.lua", i64::MAX),
+        ])));
+        assert!(matches!(
+            reader.parse(),
+            Err(GmadError::EntryPastEnd { .. })
+        ));
+
+        let long_name = vec![b'a'; MAX_STRING_LENGTH + 1];
+        let mut reader = Reader::new(Cursor::new(raw_addon(&[(&long_name, 0)])));
+        assert!(matches!(reader.parse(), Err(GmadError::StringTooLong)));
+
+        // Zero sized entries at the very end are fine
+        let mut reader = Reader::new(Cursor::new(raw_addon(&[(b"lua/a.lua", 0)])));
+        reader.parse().unwrap();
+        assert!(reader.required_content().is_empty());
+        assert_eq!(reader.read_file_slice(1), Some(&b""[..]));
+    }
+
+    #[test]
+    fn test_verify() {
+        let mut data = example_addon().into_vec();
+
+        let mut reader = Reader::new(Cursor::new(data.clone()));
+        reader.parse().unwrap();
+        let report = reader.verify().unwrap();
+        assert!(report.is_ok());
+        assert!(report.corrupt.is_empty());
+        assert!(report.addon_crc.unwrap().is_ok());
+        reader.write_file_checked(1, &mut Vec::new()).unwrap();
+
+        // Flip a byte of the contents
+        let contents = data.windows(7).position(|w| w == b"print()").unwrap();
+        data[contents] = b'P';
+
+        let mut reader = Reader::new(Cursor::new(data.clone()));
+        reader.parse().unwrap();
+        let report = reader.verify().unwrap();
+        assert!(!report.is_ok());
+        assert_eq!(report.corrupt.len(), 1);
+        assert_eq!(report.corrupt[0].name, "lua/autorun/init.lua");
+        assert_eq!(report.corrupt[0].expected, crc32::easy(b"print()"));
+        assert_eq!(report.corrupt[0].actual, crc32::easy(b"Print()"));
+        assert!(!report.addon_crc.unwrap().is_ok());
+
+        let mut out = Vec::new();
+        assert!(matches!(
+            reader.write_file_checked(1, &mut out),
+            Err(GmadError::CrcMismatch { .. })
+        ));
+        assert_eq!(out, b"Print()");
+
+        // No trailing CRC at all
+        data.truncate(data.len() - 4);
+        let mut reader = Reader::new(Cursor::new(data));
+        reader.parse().unwrap();
+        assert_eq!(reader.verify().unwrap().addon_crc, None);
+    }
+
+    #[test]
+    fn test_reader_non_utf8_names() {
+        // cp1252, the way the C++ gmad writes names on Windows
+        let mut reader = Reader::new(Cursor::new(raw_addon(&[
+            (b"lua/caf\xe9.lua", 0),
+            (b"lua/caf\xe8.lua", 0),
+        ])));
+        reader.parse().unwrap();
+        assert_eq!(reader.get_list()[0].str_name, "lua/caf\u{e9}.lua");
+        assert_eq!(reader.get_list()[1].str_name, "lua/caf\u{e8}.lua");
+        assert_eq!(reader.find("lua/caf\u{e9}.lua").unwrap().i_file_number, 1);
+    }
+
+    #[test]
+    fn test_reader_deeply_nested_description() {
+        // Too deep to import, so it's kept as it is instead of overflowing
+        // the stack
+        let description = "[".repeat(200_000);
+        let data = raw_addon(&[(b"lua/a.lua", 0)]);
+        let at = data
+            .windows(12)
+            .position(|w| w == b"Description\0")
+            .unwrap();
+        let data = [&data[..at], description.as_bytes(), &data[at + 11..]].concat();
+
+        let mut reader = Reader::new(Cursor::new(data));
+        reader.parse().unwrap();
+        assert_eq!(reader.description(), description);
+        assert_eq!(reader.addon_type(), "");
+    }
+
+    #[test]
+    fn test_reader_errors() {
+        let data = example_addon().into_vec();
+
+        let mut reader = Reader::new(Cursor::new(b"GMAX\x03".to_vec()));
+        assert!(matches!(reader.parse(), Err(GmadError::BadIdent)));
+
+        let mut reader = Reader::new(Cursor::new(b"GMAD\x09".to_vec()));
+        assert!(matches!(
+            reader.parse(),
+            Err(GmadError::UnsupportedVersion(9))
+        ));
+
+        let mut reader = Reader::new(Cursor::new(b"GMAD\x00".to_vec()));
+        assert!(matches!(
+            reader.parse(),
+            Err(GmadError::UnsupportedVersion(0))
+        ));
+
+        let mut reader = Reader::new(Cursor::new(data[..60].to_vec()));
+        assert!(matches!(reader.parse(), Err(GmadError::TruncatedHeader)));
+
+        // Cut inside the first entry's name
+        let index = data.windows(4).position(|w| w == b"lua/").unwrap();
+        let mut reader = Reader::new(Cursor::new(data[..index + 2].to_vec()));
+        assert!(matches!(reader.parse(), Err(GmadError::TruncatedIndex)));
+
+        // Cut inside the file contents
+        let contents = data.windows(7).position(|w| w == b"print()").unwrap();
+        let mut reader = Reader::new(Cursor::new(data[..contents + 3].to_vec()));
+        assert!(matches!(
+            reader.parse(),
+            Err(GmadError::EntryPastEnd { .. })
+        ));
+
+        let err = Reader::open("does/not/exist.gma").err().unwrap();
+        assert!(err.to_string().starts_with("does/not/exist.gma: "));
+    }
+}
@@ -0,0 +1,84 @@
+use std::io::{self, Write};
+
+use bootil::data::json;
+use bootil::data::tree::Tree;
+use bootil::{output, warning, BString};
+
+/// The result of a command, printed as a JSON document when `-json` is
+/// given. While it's enabled the usual output is captured, so nothing else
+/// ends up on stdout. Without it the report does nothing and errors are
+/// printed as warnings, same as always.
+pub struct Report {
+    enabled: bool,
+    tree: Tree,
+    errors: Vec<BString>,
+}
+
+impl Report {
+    pub fn new(enabled: bool, str_command: &str) -> Report {
+        if enabled {
+            output::set_capture(true);
+        }
+
+        let mut tree = Tree::new();
+        tree.set_child("command", str_command);
+        Report {
+            enabled,
+            tree,
+            errors: Vec::new(),
+        }
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.enabled
+    }
+
+    pub fn tree(&mut self) -> &mut Tree {
+        &mut self.tree
+    }
+
+    /// Records an error that stops the command.
+    pub fn error(&mut self, str_error: &str) {
+        if self.enabled {
+            self.errors.push(str_error.into());
+        } else {
+            warning!("{}\n", str_error);
+        }
+    }
+
+    /// `error` followed by `finish(1)`.
+    pub fn fail(mut self, str_error: &str) -> i32 {
+        self.error(str_error);
+        self.finish(1)
+    }
+
+    /// Prints the document, with the warnings captured along the way, and
+    /// hands back the exit code.
+    pub fn finish(mut self, code: i32) -> i32 {
+        if !self.enabled {
+            return code;
+        }
+
+        self.tree.set_child_bool("success", code == 0);
+
+        let warning_list = self.tree.add_array("warnings");
+        for str_warning in output::take_warnings() {
+            warning_list.add_child("").set_value(&str_warning);
+        }
+
+        let error_list = self.tree.add_array("errors");
+        for str_error in &self.errors {
+            error_list.add_child("").set_value(str_error);
+        }
+
+        let mut str_output = BString::new();
+        json::export(&self.tree, &mut str_output, true);
+        str_output.push('\n');
+
+        let mut stdout = io::stdout();
+        let _ = stdout.write_all(str_output.as_bytes());
+        let _ = stdout.flush();
+
+        code
+    }
+}
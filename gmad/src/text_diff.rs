@@ -0,0 +1,188 @@
+//! Line based diffs of text files, in unified diff format.
+
+/// Lines of unchanged context around each change.
+pub const CONTEXT: usize = 3;
+
+/// Past this many (lines in old * lines in new) after trimming the common
+/// start and end, the changed part is shown as one big replacement instead.
+const MAX_TABLE_SIZE: usize = 4 * 1024 * 1024;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Edit {
+    Equal(usize, usize),
+    Delete(usize),
+    Insert(usize),
+}
+
+/// The edits turning `old` into `new`, from the longest common subsequence.
+fn diff_lines(old: &[&str], new: &[&str]) -> Vec<Edit> {
+    let prefix = old.iter().zip(new).take_while(|(a, b)| a == b).count();
+    let suffix = old[prefix..]
+        .iter()
+        .rev()
+        .zip(new[prefix..].iter().rev())
+        .take_while(|(a, b)| a == b)
+        .count();
+    let (old_end, new_end) = (old.len() - suffix, new.len() - suffix);
+
+    let mut edits: Vec<Edit> = (0..prefix).map(|i| Edit::Equal(i, i)).collect();
+
+    let old_mid = &old[prefix..old_end];
+    let new_mid = &new[prefix..new_end];
+    let width = new_mid.len() + 1;
+    if old_mid.len().saturating_mul(new_mid.len()) > MAX_TABLE_SIZE {
+        edits.extend((prefix..old_end).map(Edit::Delete));
+        edits.extend((prefix..new_end).map(Edit::Insert));
+    } else {
+        // lcs[i * width + j] is the LCS length of old_mid[i..] and new_mid[j..]
+        let mut lcs = vec![0u32; (old_mid.len() + 1) * width];
+        for i in (0..old_mid.len()).rev() {
+            for j in (0..new_mid.len()).rev() {
+                lcs[i * width + j] = if old_mid[i] == new_mid[j] {
+                    lcs[(i + 1) * width + j + 1] + 1
+                } else {
+                    lcs[(i + 1) * width + j].max(lcs[i * width + j + 1])
+                };
+            }
+        }
+
+        let (mut i, mut j) = (0, 0);
+        while i < old_mid.len() || j < new_mid.len() {
+            if i < old_mid.len() && j < new_mid.len() && old_mid[i] == new_mid[j] {
+                edits.push(Edit::Equal(prefix + i, prefix + j));
+                i += 1;
+                j += 1;
+            } else if j == new_mid.len()
+                || (i < old_mid.len() && lcs[(i + 1) * width + j] >= lcs[i * width + j + 1])
+            {
+                edits.push(Edit::Delete(prefix + i));
+                i += 1;
+            } else {
+                edits.push(Edit::Insert(prefix + j));
+                j += 1;
+            }
+        }
+    }
+
+    edits.extend((0..suffix).map(|k| Edit::Equal(old_end + k, new_end + k)));
+    edits
+}
+
+fn push_line(out: &mut String, prefix: char, line: &str) {
+    out.push(prefix);
+    out.push_str(line);
+    if !line.ends_with('\n') {
+        out.push_str("\n\\ No newline at end of file\n");
+    }
+}
+
+/// A unified diff between two texts, or an empty string if they're the same.
+pub fn unified_diff(old_name: &str, new_name: &str, old: &str, new: &str) -> String {
+    let old_lines: Vec<&str> = old.split_inclusive('\n').collect();
+    let new_lines: Vec<&str> = new.split_inclusive('\n').collect();
+    let edits = diff_lines(&old_lines, &new_lines);
+
+    let changes: Vec<usize> = (0..edits.len())
+        .filter(|&k| !matches!(edits[k], Edit::Equal(..)))
+        .collect();
+    if changes.is_empty() {
+        return String::new();
+    }
+
+    let mut out = format!("--- {}\n+++ {}\n", old_name, new_name);
+
+    // Changes close enough to share their context go in the same hunk
+    let mut first = 0;
+    while first < changes.len() {
+        let mut last = first;
+        while last + 1 < changes.len() && changes[last + 1] - changes[last] <= 2 * CONTEXT + 1 {
+            last += 1;
+        }
+
+        let start = changes[first].saturating_sub(CONTEXT);
+        let end = (changes[last] + CONTEXT + 1).min(edits.len());
+        let hunk = &edits[start..end];
+
+        // Where the hunk starts in each file: the lines of that file before it
+        let old_before = edits[..start]
+            .iter()
+            .filter(|e| !matches!(e, Edit::Insert(_)))
+            .count();
+        let new_before = edits[..start]
+            .iter()
+            .filter(|e| !matches!(e, Edit::Delete(_)))
+            .count();
+        let old_count = hunk
+            .iter()
+            .filter(|e| !matches!(e, Edit::Insert(_)))
+            .count();
+        let new_count = hunk
+            .iter()
+            .filter(|e| !matches!(e, Edit::Delete(_)))
+            .count();
+
+        // An empty range is given as the line before it
+        let old_start = if old_count == 0 {
+            old_before
+        } else {
+            old_before + 1
+        };
+        let new_start = if new_count == 0 {
+            new_before
+        } else {
+            new_before + 1
+        };
+        out.push_str(&format!(
+            "@@ -{},{} +{},{} @@\n",
+            old_start, old_count, new_start, new_count
+        ));
+
+        for edit in hunk {
+            match *edit {
+                Edit::Equal(i, _) => push_line(&mut out, ' ', old_lines[i]),
+                Edit::Delete(i) => push_line(&mut out, '-', old_lines[i]),
+                Edit::Insert(j) => push_line(&mut out, '+', new_lines[j]),
+            }
+        }
+
+        first = last + 1;
+    }
+
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_unified_diff() {
+        assert_eq!(unified_diff("a", "b", "same\n", "same\n"), "");
+
+        let old = "1\n2\n3\n4\n5\n6\n7\n8\n9\n10\n11\n12\n13\n14\n15\n";
+        let new = "1\n2\nthree\n4\n5\n6\n7\n8\n9\n10\n11\n12\n13\n14\n15\n16\n";
+        assert_eq!(
+            unified_diff("a/x.lua", "b/x.lua", old, new),
+            "--- a/x.lua\n+++ b/x.lua\n\
+             @@ -1,6 +1,6 @@\n 1\n 2\n-3\n+three\n 4\n 5\n 6\n\
+             @@ -13,3 +13,4 @@\n 13\n 14\n 15\n+16\n"
+        );
+
+        // Close changes share a hunk
+        let old = "a\nb\nc\nd\ne\n";
+        let new = "A\nb\nc\nd\nE\n";
+        assert_eq!(
+            unified_diff("a", "b", old, new),
+            "--- a\n+++ b\n@@ -1,5 +1,5 @@\n-a\n+A\n b\n c\n d\n-e\n+E\n"
+        );
+
+        assert_eq!(
+            unified_diff("a", "b", "", "new\n"),
+            "--- a\n+++ b\n@@ -0,0 +1,1 @@\n+new\n"
+        );
+        assert_eq!(
+            unified_diff("a", "b", "end\n", "end"),
+            "--- a\n+++ b\n@@ -1,1 +1,1 @@\n-end\n+end\n\\ No newline at end of file\n"
+        );
+    }
+}
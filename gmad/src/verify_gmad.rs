@@ -0,0 +1,92 @@
+use bootil::{msg, warning, BString};
+
+use crate::reader::Reader;
+use crate::report::Report;
+
+pub fn verify_addon_file(str_file: BString, json: bool) -> i32 {
+    let mut report = Report::new(json, "verify");
+    report.tree().set_child("file", &str_file);
+
+    msg!("Verifying \"{}\"\n", str_file);
+
+    let mut addon = match Reader::open(&str_file) {
+        Ok(addon) => addon,
+        Err(err) => return report.fail(&format!("There was a problem opening the file: {}", err)),
+    };
+
+    if let Err(err) = addon.parse() {
+        return report.fail(&format!("There was a problem parsing the file: {}", err));
+    }
+
+    let result = match addon.verify() {
+        Ok(result) => result,
+        Err(err) => return report.fail(&format!("There was a problem reading the file: {}", err)),
+    };
+
+    for entry in &result.corrupt {
+        warning!(
+            "\t{} [CRC {:08x}, expected {:08x}]\n",
+            entry.name,
+            entry.actual,
+            entry.expected
+        );
+    }
+
+    for str_name in &result.unchecked {
+        msg!("\t{} [no CRC stored]\n", str_name);
+    }
+
+    msg!(
+        "Checked {} files: {} corrupt, {} without a CRC\n",
+        addon.len(),
+        result.corrupt.len(),
+        result.unchecked.len()
+    );
+
+    match &result.addon_crc {
+        Some(crc) if crc.is_ok() => msg!("Addon CRC: OK [{:08x}]\n", crc.actual),
+        Some(crc) => warning!(
+            "Addon CRC: mismatch [{:08x}, expected {:08x}]\n",
+            crc.actual,
+            crc.expected
+        ),
+        None => msg!("Addon CRC: not stored\n"),
+    }
+
+    if report.is_enabled() {
+        let tree = report.tree();
+        tree.set_child_number("files", addon.len());
+        let corrupt_list = tree.add_array("corrupt");
+        for entry in &result.corrupt {
+            let item = corrupt_list.add_object("");
+            item.set_child_number("number", entry.file_number);
+            item.set_child("name", &entry.name);
+            item.set_child("crc", &format!("{:08x}", entry.actual));
+            item.set_child("expected", &format!("{:08x}", entry.expected));
+        }
+        let unchecked_list = tree.add_array("unchecked");
+        for str_name in &result.unchecked {
+            unchecked_list.add_child("").set_value(str_name);
+        }
+        match &result.addon_crc {
+            Some(crc) => {
+                let item = tree.add_object("addon_crc");
+                item.set_child("crc", &format!("{:08x}", crc.actual));
+                item.set_child("expected", &format!("{:08x}", crc.expected));
+                item.set_child_bool("ok", crc.is_ok());
+            }
+            None => {
+                tree.add_child("addon_crc").set_null();
+            }
+        }
+        tree.set_child_bool("ok", result.is_ok());
+    }
+
+    if result.is_ok() {
+        msg!("Done!\n");
+        report.finish(0)
+    } else {
+        report.error("The addon is corrupt");
+        report.finish(1)
+    }
+}
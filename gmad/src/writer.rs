@@ -0,0 +1,263 @@
+use std::io::{self, Read, Write};
+
+use bootil::hasher::crc32::Crc32;
+use bootil::{AutoBuffer, BString, BinaryWrite};
+
+use crate::error::GmadError;
+use crate::format::{self, AddonHeader, FileEntry, IDENT};
+
+/// Writes an addon to a stream a piece at a time: the header and index,
+/// then each file's contents, then the CRC of everything before it. Only the
+/// header and index are ever held in memory.
+pub struct Writer<W: Write> {
+    out: W,
+    path: BString,
+    crc: Crc32,
+    written: u64,
+}
+
+impl<W: Write> Writer<W> {
+    pub fn new(out: W) -> Writer<W> {
+        Writer {
+            out,
+            path: BString::new(),
+            crc: Crc32::new(),
+            written: 0,
+        }
+    }
+
+    /// Names the file being written, for error messages.
+    pub fn with_path(mut self, path: &str) -> Self {
+        self.path = path.into();
+        self
+    }
+
+    /// Writes the header and the file index. The file numbers and offsets of
+    /// `entries` are ignored; files are numbered in the order given, and
+    /// their contents have to be written in that order too.
+    pub fn write_header(
+        &mut self,
+        header: &AddonHeader,
+        entries: &[FileEntry],
+    ) -> Result<(), GmadError> {
+        if u32::try_from(entries.len()).is_err() {
+            return Err(GmadError::TooManyFiles);
+        }
+        if !format::version_supported(header.format_version) {
+            return Err(GmadError::UnsupportedVersion(header.format_version));
+        }
+        if !format::has_required_content(header.format_version)
+            && !header.required_content.is_empty()
+        {
+            return Err(GmadError::InvalidMetadata(format!(
+                "format version {} can't hold required content",
+                header.format_version
+            )));
+        }
+
+        // Writing to a buffer only fails on strings that contain a NUL
+        let mut buffer = AutoBuffer::new();
+        header_to_buffer(&mut buffer, header, entries)
+            .map_err(|e| GmadError::InvalidMetadata(e.to_string()))?;
+
+        self.write(buffer.get_base())
+    }
+
+    /// Copies a file's contents from `input`, which has to hold exactly as
+    /// many bytes as the index says.
+    pub fn write_contents<R: Read + ?Sized>(
+        &mut self,
+        entry: &FileEntry,
+        input: &mut R,
+    ) -> Result<(), GmadError> {
+        let mut copied: u64 = 0;
+        let mut chunk = vec![0u8; (entry.i_size.max(0) as u64).clamp(1, 64 * 1024) as usize];
+        loop {
+            let count = match input.read(&mut chunk) {
+                Ok(0) => break,
+                Ok(count) => count,
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+                Err(e) => return Err(GmadError::io(&entry.str_name, e)),
+            };
+
+            copied += count as u64;
+            if copied > entry.i_size as u64 {
+                break;
+            }
+            self.write(&chunk[..count])?;
+        }
+
+        if copied != entry.i_size as u64 {
+            return Err(GmadError::SizeChanged {
+                name: entry.str_name.clone(),
+                expected: entry.i_size,
+            });
+        }
+
+        Ok(())
+    }
+
+    /// Writes the addon CRC, or 0 when `with_crc` isn't set, and flushes.
+    /// Hands back the stream and the number of bytes written.
+    pub fn finish(mut self, with_crc: bool) -> Result<(W, u64), GmadError> {
+        let addon_crc = if with_crc { self.crc.finalize() } else { 0 };
+        self.write(&addon_crc.to_le_bytes())?; // Addon CRC (4)
+        self.out.flush().map_err(|e| GmadError::io(&self.path, e))?;
+        Ok((self.out, self.written))
+    }
+
+    /// Bytes written so far.
+    pub fn get_written(&self) -> u64 {
+        self.written
+    }
+
+    fn write(&mut self, data: &[u8]) -> Result<(), GmadError> {
+        self.out
+            .write_all(data)
+            .map_err(|e| GmadError::io(&self.path, e))?;
+        self.crc.update(data);
+        self.written += data.len() as u64;
+        Ok(())
+    }
+}
+
+fn header_to_buffer(
+    buffer: &mut AutoBuffer,
+    header: &AddonHeader,
+    entries: &[FileEntry],
+) -> io::Result<()> {
+    buffer.write_all(IDENT)?; // Ident (4)
+    buffer.write_u8(header.format_version)?; // Version (1)
+    buffer.write_u64(header.steam_id)?; // SteamID (8) [unused]
+    buffer.write_u64(header.timestamp)?; // TimeStamp (8)
+    if format::has_required_content(header.format_version) {
+        for str_content in &header.required_content {
+            buffer.write_cstring(str_content)?; // Required content (a list of strings)
+        }
+        buffer.write_cstring("")?; // End of required content
+    }
+    buffer.write_cstring(&header.title)?; // Addon Name (n)
+    buffer.write_cstring(&header.description)?; // Addon Description (n)
+    buffer.write_cstring(&header.author)?; // Addon Author (n) [unused]
+    buffer.write_i32(header.addon_version)?; // Addon Version (4) [unused]
+
+    for (i, entry) in entries.iter().enumerate() {
+        buffer.write_u32(i as u32 + 1)?; // File number (4)
+        buffer.write_cstring(&entry.str_name)?; // File name (n)
+        buffer.write_i64(entry.i_size)?; // File size (8)
+        buffer.write_u32(entry.i_crc)?; // File CRC (4)
+    }
+
+    buffer.write_u32(0) // End of the index
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use super::*;
+    use crate::reader::Reader;
+
+    fn entry(str_name: &str, contents: &[u8]) -> FileEntry {
+        FileEntry {
+            str_name: str_name.into(),
+            i_size: contents.len() as i64,
+            i_crc: bootil::hasher::crc32::easy(contents),
+            ..FileEntry::default()
+        }
+    }
+
+    #[test]
+    fn test_writer() {
+        let files: [(&str, &[u8]); 2] = [
+            ("lua/autorun/init.lua", b"print()"),
+            ("materials/a.vmt", b"x"),
+        ];
+        let entries: Vec<FileEntry> = files
+            .iter()
+            .map(|(name, contents)| entry(name, contents))
+            .collect();
+
+        let mut header = AddonHeader::new("Streamed", "{}");
+        header.timestamp = 0;
+
+        let mut writer = Writer::new(Vec::new());
+        writer.write_header(&header, &entries).unwrap();
+        for (entry, (_, contents)) in entries.iter().zip(files) {
+            writer.write_contents(entry, &mut &contents[..]).unwrap();
+        }
+        let (data, written) = writer.finish(true).unwrap();
+        assert_eq!(written, data.len() as u64);
+
+        let mut reader = Reader::new(Cursor::new(data));
+        reader.parse().unwrap();
+        assert_eq!(reader.title(), "Streamed");
+        assert_eq!(reader.len(), 2);
+        assert_eq!(reader.read_file_slice(2), Some(&b"x"[..]));
+        let report = reader.verify().unwrap();
+        assert!(report.is_ok());
+        assert!(report.addon_crc.is_some());
+    }
+
+    #[test]
+    fn test_writer_format_versions() {
+        let entries = [entry("lua/a.lua", b"print()")];
+
+        for format_version in 1..=3 {
+            let mut header = AddonHeader::new("Old", "{}");
+            header.format_version = format_version;
+            if format_version > 1 {
+                header.required_content = vec!["base_content".into()];
+            }
+
+            let mut writer = Writer::new(Vec::new());
+            writer.write_header(&header, &entries).unwrap();
+            writer
+                .write_contents(&entries[0], &mut &b"print()"[..])
+                .unwrap();
+            let (data, _) = writer.finish(true).unwrap();
+            assert_eq!(data[4], format_version);
+
+            let mut reader = Reader::new(Cursor::new(data));
+            reader.parse().unwrap();
+            assert_eq!(reader.get_format_version(), format_version);
+            assert_eq!(reader.title(), "Old");
+            assert_eq!(reader.required_content(), header.required_content);
+            assert_eq!(reader.read_file_slice(1), Some(&b"print()"[..]));
+            assert!(reader.verify().unwrap().is_ok());
+        }
+
+        let mut header = AddonHeader::new("Old", "{}");
+        header.format_version = 1;
+        header.required_content = vec!["base_content".into()];
+        assert!(matches!(
+            Writer::new(Vec::new()).write_header(&header, &entries),
+            Err(GmadError::InvalidMetadata(_))
+        ));
+
+        for format_version in [0, 4] {
+            header.format_version = format_version;
+            assert!(matches!(
+                Writer::new(Vec::new()).write_header(&header, &entries),
+                Err(GmadError::UnsupportedVersion(v)) if v == format_version
+            ));
+        }
+    }
+
+    #[test]
+    fn test_writer_size_changed() {
+        let indexed = entry("lua/a.lua", b"print()");
+
+        let mut writer = Writer::new(Vec::new());
+        assert!(matches!(
+            writer.write_contents(&indexed, &mut &b"print(1)"[..]),
+            Err(GmadError::SizeChanged { .. })
+        ));
+
+        let mut writer = Writer::new(Vec::new());
+        assert!(matches!(
+            writer.write_contents(&indexed, &mut &b"print"[..]),
+            Err(GmadError::SizeChanged { .. })
+        ));
+    }
+}